@@ -0,0 +1,281 @@
+//! Generates a builder module for every element in gen_builders/elements.json,
+//! src/builders.rs includes them
+
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+const ELEMENTS: &str = "gen_builders/elements.json";
+const BOOLEAN_ATTRS: &str = "gen_builders/boolean_attrs.json";
+const EMPTY_TAGS: &str = "gen_builders/empty_tags.json";
+
+struct Element {
+    tag: String,
+    attrs: Vec<String>,
+    has_global: bool,
+    has_events: bool,
+}
+
+fn read_json(path: &str) -> Value {
+    println!("cargo:rerun-if-changed={}", path);
+    let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+    serde_json::from_str(&text).unwrap_or_else(|err| panic!("{}: {}", path, err))
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .expect("expected an array of strings")
+        .iter()
+        .map(|item| item.as_str().expect("expected a string").to_string())
+        .collect()
+}
+
+fn elements(value: &Value) -> Vec<Element> {
+    value
+        .as_array()
+        .expect("expected an array of elements")
+        .iter()
+        .map(|element| Element {
+            tag: element["tag"].as_str().expect("tag").to_string(),
+            attrs: strings(&element["attr"]),
+            has_global: element["has_global"].as_bool().expect("has_global"),
+            has_events: element["has_events"].as_bool().expect("has_events"),
+        })
+        .collect()
+}
+
+/// Returns the name as a valid Rust identifier
+fn fix_name(name: &str) -> String {
+    match name {
+        "type" | "loop" | "for" | "async" => format!("r#{}", name),
+        _ => name.replace('-', "_"),
+    }
+}
+
+fn builder_name(tag: &str) -> String {
+    let mut chars = tag.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn attr_fun(name: &str, boolean_attrs: &HashSet<String>) -> String {
+    if boolean_attrs.contains(name) {
+        format!(
+            "
+    pub fn {safe}(mut self) -> Self {{
+        self.attrs.push(Attr::name_only(\"{name}\"));
+        self
+    }}
+",
+            safe = fix_name(name),
+            name = name
+        )
+    } else {
+        format!(
+            "
+    pub fn {safe}<T: Into<String>>(mut self, value: T) -> Self {{
+        self.attrs.push(Attr::new(\"{name}\", value));
+        self
+    }}
+",
+            safe = fix_name(name),
+            name = name
+        )
+    }
+}
+
+const CHILD_FUNS: &str = "
+    pub fn el(mut self, node: impl NodeBuilder) -> Self {
+        self.children.push(node.node());
+        self
+    }
+
+    pub fn els(mut self, nodes: Vec<impl NodeBuilder>) -> Self {
+        for node in nodes {
+            self.children.push(node.node());
+        }
+        self
+    }
+
+    pub fn text<T: Into<String>>(mut self, text: T) -> Self {
+        self.children.push(Node::new_text(text));
+        self
+    }
+
+    pub fn nodes(mut self, nodes: impl IntoNodes) -> Self {
+        self.children.extend(nodes.into_nodes());
+        self
+    }
+";
+
+fn element_module(element: &Element, boolean_attrs: &HashSet<String>, is_empty: bool) -> String {
+    let tag = &element.tag;
+    let builder = builder_name(tag);
+    let attr_funs: String = element
+        .attrs
+        .iter()
+        .map(|attr| attr_fun(attr, boolean_attrs))
+        .collect();
+    let (child_vec_decl, child_vec_inst, child_funs, children) = if is_empty {
+        ("", "", "", "Vec::<Node>::new()")
+    } else {
+        (
+            "\n    children: Vec<Node>,",
+            "\n            children: Vec::<Node>::new(),",
+            CHILD_FUNS,
+            "self.children",
+        )
+    };
+
+    let mut imports = vec!["NodeBuilder"];
+    if !is_empty {
+        imports.push("IntoNodes");
+    }
+    let mut trait_impls = String::new();
+    if element.has_events {
+        imports.push("EventAttr");
+        trait_impls.push_str(&format!("\nimpl EventAttr for {} {{}}\n", builder));
+    }
+    if element.has_global {
+        imports.push("GlobalAttr");
+        trait_impls.push_str(&format!("\nimpl GlobalAttr for {} {{}}\n", builder));
+    }
+
+    format!(
+        "
+pub mod {module} {{
+use crate::html::{{Attr, EventKind, EventOptions, Handler, Node}};
+use crate::builders::{{{imports}}};
+use crate::builders::set_attr::SetAttr;
+
+/// Builds a <{tag}> element
+#[derive(Debug)]
+pub struct {builder} {{
+    events: Vec<(EventKind, EventOptions)>,
+    handlers: Vec<Handler>,
+    attrs: Vec<Attr>,
+    // Spelled out since the builder for <option> is named Option
+    key: std::option::Option<String>,{child_vec_decl}
+}}
+
+impl {builder} {{
+    pub fn new() -> Self {{
+        Self {{
+            events: Vec::new(),
+            handlers: Vec::<Handler>::new(),
+            attrs: Vec::<Attr>::new(),
+            key: None,{child_vec_inst}
+        }}
+    }}
+
+    pub fn event(mut self, event_kind: EventKind) -> Self {{
+        self.events.push((event_kind, EventOptions::default()));
+        self
+    }}
+
+    pub fn events(mut self, event_kinds: Vec<EventKind>) -> Self {{
+        for event_kind in event_kinds {{
+            self.events.push((event_kind, EventOptions::default()));
+        }}
+        self
+    }}
+
+    /// Sends the event to `App::update` the way the options say
+    pub fn event_with(mut self, event_kind: EventKind, options: EventOptions) -> Self {{
+        self.events.push((event_kind, options));
+        self
+    }}
+
+    /// Binds a closure to an event, it is called with the App handling the connection
+    /// instead of `App::update`. A is the App type, a closure written for another type
+    /// is reported to `App::on_error` and its events go to `App::update`
+    pub fn on<A, F>(mut self, event_kind: EventKind, handler: F) -> Self
+    where
+        A: 'static,
+        F: Fn(&mut A, &crate::Message) -> Vec<crate::Command> + Send + Sync + 'static,
+    {{
+        self.handlers.push(Handler::new(event_kind, EventOptions::default(), handler));
+        self
+    }}
+
+    /// Binds a closure to an event like `on`, the event is sent the way the options say
+    pub fn on_with<A, F>(mut self, event_kind: EventKind, options: EventOptions, handler: F) -> Self
+    where
+        A: 'static,
+        F: Fn(&mut A, &crate::Message) -> Vec<crate::Command> + Send + Sync + 'static,
+    {{
+        self.handlers.push(Handler::new(event_kind, options, handler));
+        self
+    }}
+
+    /// Identifies the element among its siblings, keyed elements are moved rather than
+    /// rebuilt when the children of a view are reordered, see `App::view`
+    pub fn key<T: Into<String>>(mut self, key: T) -> Self {{
+        self.key = Some(key.into());
+        self
+    }}
+{attr_funs}{child_funs}}}
+
+impl Default for {builder} {{
+    fn default() -> Self {{
+        Self::new()
+    }}
+}}
+
+impl NodeBuilder for {builder} {{
+    fn node(self) -> Node {{
+        Node::new_el(\"{tag}\", self.events, self.attrs, {children}, {is_empty}, self.key, self.handlers)
+    }}
+}}
+
+impl SetAttr for {builder} {{
+    fn set_attr<T: Into<String>>(mut self, name: &'static str, value: T) -> Self {{
+        self.attrs.push(Attr::new(name, value));
+        self
+    }}
+}}
+{trait_impls}
+/// Returns a builder for a <{tag}> element
+pub fn {module}() -> {builder} {{
+    {builder}::new()
+}}
+}}
+pub use self::{module}::{module};
+",
+        module = fix_name(tag),
+        imports = imports.join(", "),
+        tag = tag,
+        builder = builder,
+        child_vec_decl = child_vec_decl,
+        child_vec_inst = child_vec_inst,
+        attr_funs = attr_funs,
+        child_funs = child_funs,
+        children = children,
+        is_empty = is_empty,
+        trait_impls = trait_impls,
+    )
+}
+
+fn main() {
+    let elements = elements(&read_json(ELEMENTS));
+    let boolean_attrs: HashSet<String> = strings(&read_json(BOOLEAN_ATTRS)).into_iter().collect();
+    let empty_tags: HashSet<String> = strings(&read_json(EMPTY_TAGS)).into_iter().collect();
+
+    let code: String = elements
+        .iter()
+        .map(|element| {
+            let is_empty = empty_tags.contains(&element.tag);
+            element_module(element, &boolean_attrs, is_empty)
+        })
+        .collect();
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    let out_file = out_dir.join("builders.rs");
+    fs::write(&out_file, code).unwrap_or_else(|err| panic!("{}: {}", out_file.display(), err));
+}
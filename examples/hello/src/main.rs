@@ -1,13 +1,14 @@
 use lowui::builders::*;
 use lowui::html;
 use lowui::html::EventKind;
+use std::sync::Arc;
 
 struct Application {
     ids: Vec<String>,
 }
 
 impl lowui::App for Application {
-    fn new() -> Application {
+    fn new(_shared: Arc<()>) -> Application {
         Application {
             ids: Vec::<String>::new(),
         }
@@ -81,10 +82,10 @@ impl lowui::App for Application {
     }
 }
 
-fn main() {
+fn main() -> lowui::Result<()> {
     let mut path = std::path::PathBuf::from(std::env::current_exe().unwrap().parent().unwrap());
     path.push("static");
     path.push("index.css");
     println!("{:?}", path);
-    lowui::start::<Application>();
+    lowui::start::<Application>()
 }
@@ -1,6 +1,7 @@
 use lowui::builders::*;
 use lowui::html;
 use lowui::html::EventKind;
+use std::sync::Arc;
 
 struct Application {}
 
@@ -11,7 +12,7 @@ impl Application {
 }
 
 impl lowui::App for Application {
-    fn new() -> Application {
+    fn new(_shared: Arc<()>) -> Application {
         Application {}
     }
 
@@ -62,6 +63,6 @@ impl lowui::App for Application {
     }
 }
 
-fn main() {
-    lowui::start::<Application>();
+fn main() -> lowui::Result<()> {
+    lowui::start::<Application>()
 }
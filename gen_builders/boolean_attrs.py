@@ -1,26 +0,0 @@
-boolean_attrs = [
-    "allowfullscreen",
-    "allowpaymentrequest",
-    "async",
-    "autofocus",
-    "autoplay",
-    "checked",
-    "controls",
-    "default",
-    "defer",
-    "disabled",
-    "formnovalidate",
-    "hidden",
-    "ismap",
-    "loop",
-    "multiple",
-    "muted",
-    "novalidate",
-    "open",
-    "readonly",
-    "required",
-    "reversed",
-    "selected",
-    "typemustmatch",
-]
-
@@ -0,0 +1,340 @@
+//! Procedural macros for lowui, use them through the lowui crate
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{braced, parse_macro_input, token, Expr, Ident, LitStr, Pat, Token};
+
+/// Builds an `html::Node` from HTML-like markup, using the element builders.
+///
+/// Text is written as string literals and Rust expressions go in braces,
+/// both as attribute values and as children. A child expression can be anything that
+/// implements `builders::IntoNodes`, such as a `Node`, a builder, a string or a `Vec` of them.
+/// Children can also be generated with `for` loops and `if`/`else`.
+/// Attributes without a value call the builder method without arguments,
+/// `data-*` attributes call `data` and `key` sets the element's key.
+/// Every attribute must be a method of the element's builder,
+/// so attributes the element does not have are compile errors.
+///
+/// ```
+/// use lowui::html;
+///
+/// let items = vec!["one", "two"];
+/// let node = html! {
+///     <ul id="list" class={format!("items-{}", items.len())}>
+///         for item in &items {
+///             <li key={*item}>{*item}</li>
+///         }
+///         if items.is_empty() {
+///             <li>"Nothing here"</li>
+///         }
+///     </ul>
+/// };
+/// ```
+///
+/// ```compile_fail
+/// // <br> has no href
+/// let node = lowui::html! { <br href="/" /> };
+/// ```
+#[proc_macro]
+pub fn html(input: TokenStream) -> TokenStream {
+    let root = parse_macro_input!(input as Root);
+    let element = root.0.to_tokens();
+    TokenStream::from(quote! {
+        {
+            #[allow(unused_imports)]
+            use ::lowui::builders::{EventAttr as _, GlobalAttr as _, NodeBuilder as _};
+            ::lowui::builders::NodeBuilder::node(#element)
+        }
+    })
+}
+
+/// The single element the markup must consist of
+struct Root(Element);
+
+impl Parse for Root {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let element = input.parse()?;
+        if !input.is_empty() {
+            return Err(input.error("expected a single root element"));
+        }
+        Ok(Root(element))
+    }
+}
+
+struct Element {
+    tag: Ident,
+    attrs: Vec<Attribute>,
+    children: Vec<Child>,
+}
+
+impl Parse for Element {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![<]>()?;
+        let tag = input.call(Ident::parse_any)?;
+        let mut attrs = Vec::new();
+        while !input.peek(Token![>]) && !input.peek(Token![/]) {
+            if input.is_empty() {
+                return Err(syn::Error::new(tag.span(), format!("unclosed <{}>", tag)));
+            }
+            attrs.push(input.parse()?);
+        }
+
+        if input.peek(Token![/]) {
+            input.parse::<Token![/]>()?;
+            input.parse::<Token![>]>()?;
+            return Ok(Element {
+                tag,
+                attrs,
+                children: Vec::new(),
+            });
+        }
+        input.parse::<Token![>]>()?;
+
+        let mut children = Vec::new();
+        while !(input.peek(Token![<]) && input.peek2(Token![/])) {
+            if input.is_empty() {
+                return Err(syn::Error::new(tag.span(), format!("missing </{}>", tag)));
+            }
+            children.push(input.parse()?);
+        }
+        input.parse::<Token![<]>()?;
+        input.parse::<Token![/]>()?;
+        let close = input.call(Ident::parse_any)?;
+        if close != tag {
+            return Err(syn::Error::new(
+                close.span(),
+                format!("expected </{}>", tag),
+            ));
+        }
+        input.parse::<Token![>]>()?;
+
+        Ok(Element {
+            tag,
+            attrs,
+            children,
+        })
+    }
+}
+
+impl Element {
+    /// Returns an expression evaluating to the element's builder
+    fn to_tokens(&self) -> TokenStream2 {
+        let tag = &self.tag;
+        let attrs = self.attrs.iter().map(Attribute::to_tokens);
+        let children = self.children.iter().map(|child| {
+            let child = child.to_tokens();
+            quote_spanned!(child.span()=> .nodes(#child))
+        });
+        quote! {
+            ::lowui::builders::#tag() #(#attrs)* #(#children)*
+        }
+    }
+}
+
+struct Attribute {
+    /// The parts of the name, data-on-off is data, on and off
+    name: Vec<Ident>,
+    value: Option<AttrValue>,
+}
+
+enum AttrValue {
+    Lit(LitStr),
+    Expr(Expr),
+}
+
+impl Parse for Attribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut name = vec![input.call(Ident::parse_any)?];
+        while input.peek(Token![-]) {
+            input.parse::<Token![-]>()?;
+            name.push(input.call(Ident::parse_any)?);
+        }
+
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if input.peek(LitStr) {
+                Some(AttrValue::Lit(input.parse()?))
+            } else if input.peek(token::Brace) {
+                let content;
+                braced!(content in input);
+                Some(AttrValue::Expr(content.parse()?))
+            } else {
+                return Err(input.error("expected a string literal or a {expression}"));
+            }
+        } else {
+            None
+        };
+
+        Ok(Attribute { name, value })
+    }
+}
+
+impl Attribute {
+    /// Returns the builder method call that sets the attribute
+    fn to_tokens(&self) -> TokenStream2 {
+        let span = self.name[0].span();
+        let name: Vec<String> = self.name.iter().map(Ident::to_string).collect();
+        let value = match &self.value {
+            Some(AttrValue::Lit(lit)) => quote!(#lit),
+            Some(AttrValue::Expr(expr)) => quote!(#expr),
+            None => TokenStream2::new(),
+        };
+
+        if name.len() > 1 && name[0] == "data" {
+            let name = name.join("-");
+            if self.value.is_none() {
+                return syn::Error::new(span, format!("{} needs a value", name)).to_compile_error();
+            }
+            return quote_spanned!(span=> .data(#name, #value));
+        }
+
+        let method = method_name(&name.join("_"), span);
+        quote_spanned!(span=> .#method(#value))
+    }
+}
+
+/// Returns the name of the builder method for an attribute,
+/// attributes named after Rust keywords are raw identifiers like r#type
+fn method_name(name: &str, span: Span) -> Ident {
+    match name {
+        "type" | "loop" | "for" | "async" => Ident::new_raw(name, span),
+        _ => Ident::new(name, span),
+    }
+}
+
+enum Child {
+    Element(Element),
+    Text(LitStr),
+    Expr(Expr),
+    For {
+        pat: Pat,
+        expr: Expr,
+        body: Vec<Child>,
+    },
+    If(If),
+}
+
+struct If {
+    cond: Expr,
+    then: Vec<Child>,
+    otherwise: Option<Else>,
+}
+
+enum Else {
+    If(Box<If>),
+    Children(Vec<Child>),
+}
+
+impl Parse for Child {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![<]) {
+            Ok(Child::Element(input.parse()?))
+        } else if input.peek(LitStr) {
+            Ok(Child::Text(input.parse()?))
+        } else if input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            Ok(Child::Expr(content.parse()?))
+        } else if input.peek(Token![for]) {
+            input.parse::<Token![for]>()?;
+            let pat = Pat::parse_multi_with_leading_vert(input)?;
+            input.parse::<Token![in]>()?;
+            let expr = Expr::parse_without_eager_brace(input)?;
+            let body = parse_children(input)?;
+            Ok(Child::For { pat, expr, body })
+        } else if input.peek(Token![if]) {
+            Ok(Child::If(input.parse()?))
+        } else {
+            Err(input.error("expected an element, a string literal, a {expression}, for or if"))
+        }
+    }
+}
+
+impl Parse for If {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<Token![if]>()?;
+        let cond = Expr::parse_without_eager_brace(input)?;
+        let then = parse_children(input)?;
+        let otherwise = if input.peek(Token![else]) {
+            input.parse::<Token![else]>()?;
+            if input.peek(Token![if]) {
+                Some(Else::If(Box::new(input.parse()?)))
+            } else {
+                Some(Else::Children(parse_children(input)?))
+            }
+        } else {
+            None
+        };
+        Ok(If {
+            cond,
+            then,
+            otherwise,
+        })
+    }
+}
+
+/// Parses children in braces, the body of a loop or branch
+fn parse_children(input: ParseStream) -> syn::Result<Vec<Child>> {
+    let content;
+    braced!(content in input);
+    let mut children = Vec::new();
+    while !content.is_empty() {
+        children.push(content.parse()?);
+    }
+    Ok(children)
+}
+
+impl Child {
+    /// Returns an expression evaluating to something that implements IntoNodes
+    fn to_tokens(&self) -> TokenStream2 {
+        match self {
+            Child::Element(element) => element.to_tokens(),
+            Child::Text(text) => quote!(#text),
+            Child::Expr(expr) => quote_spanned!(expr.span()=> #expr),
+            Child::For { pat, expr, body } => {
+                let body = children_to_nodes(body);
+                quote! {
+                    {
+                        let mut __lowui_nodes = ::std::vec::Vec::<::lowui::html::Node>::new();
+                        for #pat in #expr {
+                            __lowui_nodes.extend(#body);
+                        }
+                        __lowui_nodes
+                    }
+                }
+            }
+            Child::If(branch) => branch.to_tokens(),
+        }
+    }
+}
+
+impl If {
+    fn to_tokens(&self) -> TokenStream2 {
+        let cond = &self.cond;
+        let then = children_to_nodes(&self.then);
+        let otherwise = match &self.otherwise {
+            Some(Else::If(branch)) => branch.to_tokens(),
+            Some(Else::Children(children)) => children_to_nodes(children),
+            None => quote!(::std::vec::Vec::<::lowui::html::Node>::new()),
+        };
+        quote! {
+            if #cond { #then } else { #otherwise }
+        }
+    }
+}
+
+/// Returns an expression evaluating to a Vec of the children's nodes
+fn children_to_nodes(children: &[Child]) -> TokenStream2 {
+    let children = children.iter().map(Child::to_tokens);
+    quote! {
+        {
+            let mut __lowui_nodes = ::std::vec::Vec::<::lowui::html::Node>::new();
+            #(__lowui_nodes.extend(::lowui::builders::IntoNodes::into_nodes(#children));)*
+            __lowui_nodes
+        }
+    }
+}
@@ -1,215 +1,62 @@
-pub mod a;
-pub mod abbr;
-pub mod address;
-pub mod area;
-pub mod article;
-pub mod aside;
-pub mod audio;
-pub mod b;
-pub mod bdi;
-pub mod bdo;
-pub mod blockquote;
-pub mod br;
-pub mod button;
-pub mod canvas;
-pub mod caption;
-pub mod cite;
-pub mod code;
-pub mod col;
-pub mod colgroup;
-pub mod data;
-pub mod datalist;
-pub mod dd;
-pub mod del;
-pub mod details;
-pub mod dfn;
-pub mod dialog;
-pub mod div;
-pub mod dl;
-pub mod dt;
-pub mod em;
-pub mod embed;
-pub mod fieldset;
-pub mod figcaption;
-pub mod figure;
-pub mod footer;
-pub mod form;
-pub mod h1;
-pub mod h2;
-pub mod h3;
-pub mod h4;
-pub mod h5;
-pub mod h6;
-pub mod header;
-pub mod hr;
-pub mod i;
-pub mod iframe;
-pub mod img;
-pub mod input;
-pub mod ins;
-pub mod kbd;
-pub mod label;
-pub mod legend;
-pub mod li;
-pub mod main;
-pub mod map;
-pub mod mark;
-pub mod meter;
-pub mod nav;
-pub mod object;
-pub mod ol;
-pub mod optgroup;
-pub mod option;
-pub mod output;
-pub mod p;
-pub mod param;
-pub mod picture;
-pub mod pre;
-pub mod progress;
-pub mod q;
-pub mod rp;
-pub mod rt;
-pub mod ruby;
-pub mod s;
-pub mod samp;
-pub mod section;
-pub mod select;
-pub mod small;
-pub mod source;
-pub mod span;
-pub mod strong;
-pub mod style;
-pub mod sub;
-pub mod summary;
-pub mod sup;
-pub mod svg;
-pub mod table;
-pub mod tbody;
-pub mod td;
-pub mod template;
-pub mod textarea;
-pub mod tfoot;
-pub mod th;
-pub mod thead;
-pub mod time;
-pub mod title;
-pub mod tr;
-pub mod track;
-pub mod u;
-pub mod ul;
-pub mod var;
-pub mod video;
-pub mod wbr;
-
-pub use a::a;
-pub use abbr::abbr;
-pub use address::address;
-pub use area::area;
-pub use article::article;
-pub use aside::aside;
-pub use audio::audio;
-pub use b::b;
-pub use bdi::bdi;
-pub use bdo::bdo;
-pub use blockquote::blockquote;
-pub use br::br;
-pub use button::button;
-pub use canvas::canvas;
-pub use caption::caption;
-pub use cite::cite;
-pub use code::code;
-pub use col::col;
-pub use colgroup::colgroup;
-pub use data::data;
-pub use datalist::datalist;
-pub use dd::dd;
-pub use del::del;
-pub use details::details;
-pub use dfn::dfn;
-pub use dialog::dialog;
-pub use div::div;
-pub use dl::dl;
-pub use dt::dt;
-pub use em::em;
-pub use embed::embed;
-pub use fieldset::fieldset;
-pub use figcaption::figcaption;
-pub use figure::figure;
-pub use footer::footer;
-pub use form::form;
-pub use h1::h1;
-pub use h2::h2;
-pub use h3::h3;
-pub use h4::h4;
-pub use h5::h5;
-pub use h6::h6;
-pub use header::header;
-pub use hr::hr;
-pub use i::i;
-pub use iframe::iframe;
-pub use img::img;
-pub use input::input;
-pub use ins::ins;
-pub use kbd::kbd;
-pub use label::label;
-pub use legend::legend;
-pub use li::li;
-pub use main::main;
-pub use map::map;
-pub use mark::mark;
-pub use meter::meter;
-pub use nav::nav;
-pub use object::object;
-pub use ol::ol;
-pub use optgroup::optgroup;
-pub use option::option;
-pub use output::output;
-pub use p::p;
-pub use param::param;
-pub use picture::picture;
-pub use pre::pre;
-pub use progress::progress;
-pub use q::q;
-pub use rp::rp;
-pub use rt::rt;
-pub use ruby::ruby;
-pub use s::s;
-pub use samp::samp;
-pub use section::section;
-pub use select::select;
-pub use small::small;
-pub use source::source;
-pub use span::span;
-pub use strong::strong;
-pub use style::style;
-pub use sub::sub;
-pub use summary::summary;
-pub use sup::sup;
-pub use svg::svg;
-pub use table::table;
-pub use tbody::tbody;
-pub use td::td;
-pub use template::template;
-pub use textarea::textarea;
-pub use tfoot::tfoot;
-pub use th::th;
-pub use thead::thead;
-pub use time::time;
-pub use title::title;
-pub use tr::tr;
-pub use track::track;
-pub use u::u;
-pub use ul::ul;
-pub use var::var;
-pub use video::video;
-pub use wbr::wbr;
+// A module with a builder for each element in gen_builders/elements.json, see build.rs
+include!(concat!(env!("OUT_DIR"), "/builders.rs"));
 
 use crate::html::Node;
 
+#[cfg(test)]
+mod tests;
+
 pub trait NodeBuilder {
     fn node(self) -> Node;
 }
 
+impl NodeBuilder for Node {
+    fn node(self) -> Node {
+        self
+    }
+}
+
+/// Values that can be added as children, such as with the `html!` macro
+pub trait IntoNodes {
+    fn into_nodes(self) -> Vec<Node>;
+}
+
+impl<T: NodeBuilder> IntoNodes for T {
+    fn into_nodes(self) -> Vec<Node> {
+        vec![self.node()]
+    }
+}
+
+impl IntoNodes for String {
+    fn into_nodes(self) -> Vec<Node> {
+        vec![Node::new_text(self)]
+    }
+}
+
+impl IntoNodes for &str {
+    fn into_nodes(self) -> Vec<Node> {
+        vec![Node::new_text(self)]
+    }
+}
+
+impl IntoNodes for &String {
+    fn into_nodes(self) -> Vec<Node> {
+        vec![Node::new_text(self.as_str())]
+    }
+}
+
+impl<T: IntoNodes> IntoNodes for Vec<T> {
+    fn into_nodes(self) -> Vec<Node> {
+        self.into_iter().flat_map(IntoNodes::into_nodes).collect()
+    }
+}
+
+impl<T: IntoNodes> IntoNodes for Option<T> {
+    fn into_nodes(self) -> Vec<Node> {
+        self.map(IntoNodes::into_nodes).unwrap_or_default()
+    }
+}
+
 mod set_attr {
     pub trait SetAttr: Sized {
         fn set_attr<T: Into<String>>(self, name: &'static str, value: T) -> Self;
@@ -0,0 +1,106 @@
+use crate::builders::*;
+use crate::html::Node;
+
+#[test]
+fn html_builds_nested_elements_and_text() {
+    let node = crate::html! {
+        <div id="main">
+            <h1>"Title"</h1>
+            <p>"1 < 2" <b>"bold"</b></p>
+        </div>
+    };
+    assert_eq!(
+        node.into_html(),
+        r#"<div id="main"><h1>Title</h1><p>1 &lt; 2<b>bold</b></p></div>"#
+    );
+}
+
+#[test]
+fn html_attributes_take_literals_expressions_or_nothing() {
+    let kind = "checkbox";
+    let node = crate::html! {
+        <form accept-charset="utf-8">
+            <label for="check" data-role={format!("{}-label", kind)}>"Check"</label>
+            <input id="check" type={kind} checked />
+        </form>
+    };
+    assert_eq!(
+        node.into_html(),
+        concat!(
+            r#"<form accept-charset="utf-8">"#,
+            r#"<label for="check" data-role="checkbox-label">Check</label>"#,
+            r#"<input id="check" type="checkbox" checked>"#,
+            "</form>"
+        )
+    );
+}
+
+#[test]
+fn html_children_can_be_expressions() {
+    let name = String::from("Ann");
+    let extra: Option<Node> = None;
+    let items = vec![li().text("a"), li().text("b")];
+    let node = crate::html! {
+        <div>
+            {&name}
+            {span().text(" & ")}
+            {Node::raw_html("<hr>")}
+            {extra}
+            <ul>{items}</ul>
+        </div>
+    };
+    assert_eq!(
+        node.into_html(),
+        "<div>Ann<span> &amp; </span><hr><ul><li>a</li><li>b</li></ul></div>"
+    );
+}
+
+#[test]
+fn html_for_loops_repeat_children() {
+    let items = ["a", "b"];
+    let node = crate::html! {
+        <ul>
+            for (i, item) in items.iter().enumerate() {
+                <li>{i.to_string()}</li>
+                <li>{*item}</li>
+            }
+        </ul>
+    };
+    assert_eq!(
+        node.into_html(),
+        "<ul><li>0</li><li>a</li><li>1</li><li>b</li></ul>"
+    );
+}
+
+#[test]
+fn html_if_chooses_children() {
+    let render = |count: Option<u32>| {
+        crate::html! {
+            <p>
+                if let Some(0) = count {
+                    "none"
+                } else if let Some(count) = count {
+                    {count.to_string()}
+                } else {
+                    "unknown"
+                }
+                if count.is_some() {
+                    " items"
+                }
+            </p>
+        }
+        .into_html()
+    };
+    assert_eq!(render(Some(0)), "<p>none items</p>");
+    assert_eq!(render(Some(3)), "<p>3 items</p>");
+    assert_eq!(render(None), "<p>unknown</p>");
+}
+
+#[test]
+fn html_key_sets_the_element_key() {
+    let node = crate::html! { <li key={"a"}>"A"</li> };
+    match node {
+        Node::Element { key, .. } => assert_eq!(key.as_deref(), Some("a")),
+        _ => panic!("expected an element"),
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::{Error, Result};
+use crate::Command;
+
+/// Uniquely identifies a client connection for the lifetime of the server
+pub type ConnectionId = u64;
+
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+pub(crate) fn next_connection_id() -> ConnectionId {
+    NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Information about an HTTP request for the page
+#[derive(Debug, Clone)]
+pub struct RequestInfo {
+    /// The requested path
+    pub path: String,
+    /// The query string, without the leading '?'
+    pub query: Option<String>,
+    /// The request headers, names are lowercase
+    pub headers: Vec<(String, String)>,
+    /// The address of the client
+    pub peer_addr: SocketAddr,
+}
+
+impl RequestInfo {
+    /// Returns the value of the first header with the given name, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of the first query parameter with the given name,
+    /// the value is not percent-decoded
+    pub fn query_param(&self, name: &str) -> Option<&str> {
+        query_param(self.query.as_deref()?, name)
+    }
+}
+
+pub(crate) fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        if parts.next() == Some(name) {
+            Some(parts.next().unwrap_or(""))
+        } else {
+            None
+        }
+    })
+}
+
+/// Information about a connected client
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    /// The unique id of this connection
+    pub id: ConnectionId,
+    /// The address of the client
+    pub peer_addr: SocketAddr,
+    /// The User-Agent header the client sent when connecting, if any
+    pub user_agent: Option<String>,
+    /// A handle to send commands to this client at any time
+    pub handle: ClientHandle,
+    /// Every client connected to the server, including this one
+    pub clients: Clients,
+}
+
+/// What wakes the thread serving a connection
+#[derive(Debug)]
+pub(crate) enum Wakeup {
+    /// Commands pushed through a ClientHandle
+    Pushed(Vec<Command>),
+    /// What a read from the client's socket returned, no bytes once it is closed
+    Read(io::Result<Vec<u8>>),
+}
+
+/// A cloneable handle that sends commands to one client from outside of `App::update`,
+/// for example from a thread watching a sensor
+#[derive(Debug, Clone)]
+pub struct ClientHandle {
+    id: ConnectionId,
+    sender: Sender<Wakeup>,
+}
+
+impl ClientHandle {
+    pub(crate) fn new(id: ConnectionId, sender: Sender<Wakeup>) -> Self {
+        Self { id, sender }
+    }
+
+    /// Returns the id of the connection this handle sends to
+    pub fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    /// Queues commands to be sent to the client,
+    /// fails if the client has disconnected
+    pub fn send(&self, commands: Vec<Command>) -> Result<()> {
+        self.sender
+            .send(Wakeup::Pushed(commands))
+            .map_err(|_| Error::Disconnected)
+    }
+}
+
+/// A cloneable registry of every connected client
+#[derive(Debug, Clone, Default)]
+pub struct Clients {
+    handles: Arc<Mutex<HashMap<ConnectionId, ClientHandle>>>,
+}
+
+impl Clients {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn insert(&self, handle: ClientHandle) {
+        self.lock().insert(handle.id(), handle);
+    }
+
+    pub(crate) fn remove(&self, id: ConnectionId) {
+        self.lock().remove(&id);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<ConnectionId, ClientHandle>> {
+        // A panic while holding the lock can not leave the map inconsistent
+        self.handles
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns the ids of all connected clients
+    pub fn ids(&self) -> Vec<ConnectionId> {
+        self.lock().keys().copied().collect()
+    }
+
+    /// Returns the number of connected clients
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if no clients are connected
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Sends commands to one client
+    pub fn send_to(&self, id: ConnectionId, commands: Vec<Command>) -> Result<()> {
+        let handle = self.lock().get(&id).cloned().ok_or(Error::Disconnected)?;
+        handle.send(commands)
+    }
+
+    /// Sends commands to every connected client
+    pub fn broadcast(&self, commands: Vec<Command>) {
+        self.send_where(commands, |_| true);
+    }
+
+    /// Sends commands to every connected client except the one given,
+    /// usually the client whose event caused the change
+    pub fn broadcast_except(&self, id: ConnectionId, commands: Vec<Command>) {
+        self.send_where(commands, |other| other != id);
+    }
+
+    fn send_where<F: Fn(ConnectionId) -> bool>(&self, commands: Vec<Command>, predicate: F) {
+        let handles = self.lock();
+        for handle in handles.values().filter(|handle| predicate(handle.id())) {
+            // Clients that went away are removed when their connection ends
+            let _ = handle.send(commands.clone());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+use std::io;
+
+/// Errors that can occur while serving a client
+#[derive(Debug)]
+pub enum Error {
+    /// Reading from or writing to a socket or file failed
+    Io(io::Error),
+    /// The client sent a malformed or oversized HTTP request
+    Http(String),
+    /// The WebSocket handshake could not be completed
+    Handshake(tungstenite::Error),
+    /// A WebSocket frame could not be read or written
+    Protocol(tungstenite::Error),
+    /// A message from the client could not be parsed
+    Decode(serde_json::Error),
+    /// Commands could not be serialized for the client
+    Encode(serde_json::Error),
+    /// A requested file does not exist
+    NotFound(String),
+    /// The client has disconnected
+    Disconnected,
+    /// A closure bound with `on` for this event type was written for another App type,
+    /// its events are passed to `App::update` instead
+    HandlerType(String),
+}
+
+/// A Result with a lowui Error
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Http(msg) => write!(f, "Bad HTTP request: {}", msg),
+            Error::Handshake(err) => write!(f, "WebSocket handshake failed: {}", err),
+            Error::Protocol(err) => write!(f, "WebSocket error: {}", err),
+            Error::Decode(err) => write!(f, "Invalid message from client: {}", err),
+            Error::Encode(err) => write!(f, "Could not encode commands: {}", err),
+            Error::NotFound(path) => write!(f, "Not found: {}", path),
+            Error::Disconnected => write!(f, "Client disconnected"),
+            Error::HandlerType(event_type) => write!(
+                f,
+                "Closure for {} events was written for another App type, they go to update",
+                event_type
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Handshake(err) | Error::Protocol(err) => Some(err),
+            Error::Decode(err) | Error::Encode(err) => Some(err),
+            Error::Http(_) | Error::NotFound(_) | Error::Disconnected | Error::HandlerType(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        Error::Protocol(err)
+    }
+}
@@ -0,0 +1,226 @@
+//! Closures bound to elements with `on`, kept for each connection and called for their events
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::html::{Callback, Handler, Node};
+use crate::vdom::id_of;
+use crate::{Command, CommandType, Error, Target};
+
+#[cfg(test)]
+mod tests;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Gives a handler with a closure an id unless it already has one
+pub(crate) fn assign_id(handler: &mut Handler) {
+    if handler.id.is_none() && handler.callback.is_some() {
+        handler.id = Some(format!("h{}", NEXT_ID.fetch_add(1, Ordering::Relaxed)));
+    }
+}
+
+/// At most this many closures of nodes sent outside the view are kept,
+/// the oldest are dropped first
+const MAX_SENT: usize = 1000;
+
+/// The handlers of everything sent to one client, T is the App handling the connection
+pub(crate) struct Handlers<T> {
+    /// Handlers of nodes sent outside the view, kept until the client no longer has them
+    sent: HashMap<String, Sent<T>>,
+    /// Handlers of the current view
+    view: HashMap<String, Callback<T>>,
+    /// The number of handlers bound to sent nodes so far
+    next_seq: u64,
+    /// Closures written for another App type
+    mismatched: Mismatched,
+}
+
+/// The closure of a node sent outside the view and where its element is on the client
+struct Sent<T> {
+    callback: Callback<T>,
+    /// The id of the element the closure is bound to, if it has one
+    element: Option<String>,
+    /// The ids of the elements its element was put in
+    ancestors: Vec<String>,
+    seq: u64,
+}
+
+impl<T: 'static> Handlers<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            sent: HashMap::new(),
+            view: HashMap::new(),
+            next_seq: 0,
+            mismatched: Mismatched::default(),
+        }
+    }
+
+    /// Gives the handlers in the node ids and keeps their closures
+    pub(crate) fn bind(&mut self, node: &mut Node) {
+        self.bind_sent(node, &mut Vec::new());
+    }
+
+    /// Drops the closures of elements the commands remove, then binds the handlers
+    /// in the nodes the commands add
+    pub(crate) fn bind_commands(&mut self, commands: &mut [Command]) {
+        for command in commands {
+            self.forget(command);
+            let mut ancestors: Vec<String> =
+                target_id(command).map(String::from).into_iter().collect();
+            match &mut command.command_type {
+                CommandType::AppendChildElement(node) => self.bind_sent(node, &mut ancestors),
+                CommandType::ReplaceChildren(nodes) => nodes
+                    .iter_mut()
+                    .for_each(|node| self.bind_sent(node, &mut ancestors)),
+                // The node's ancestors are the target's, which are not known here
+                CommandType::InsertElementBefore(node) | CommandType::ReplaceElement(node) => {
+                    self.bind(node)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Drops the closures of elements the commands remove, for commands that add no handlers
+    pub(crate) fn forget_removed(&mut self, commands: &[Command]) {
+        commands.iter().for_each(|command| self.forget(command));
+    }
+
+    /// Replaces the handlers of the view with the ones in the given view
+    pub(crate) fn bind_view(&mut self, view: &mut Node) {
+        let (handlers, mismatched) = (&mut self.view, &mut self.mismatched);
+        handlers.clear();
+        for_each_handler(view, &mut |handler| {
+            assign_id(handler);
+            if let (Some(id), Some(callback)) = (&handler.id, callback(handler, mismatched)) {
+                handlers.insert(id.clone(), callback.clone());
+            }
+        });
+    }
+
+    /// Returns the errors for closures bound since the last call that were written
+    /// for another App type
+    pub(crate) fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.mismatched.errors)
+    }
+
+    /// Returns the closure bound with the given id
+    pub(crate) fn get(&self, id: &str) -> Option<Callback<T>> {
+        self.view
+            .get(id)
+            .or_else(|| self.sent.get(id).map(|sent| &sent.callback))
+            .cloned()
+    }
+
+    /// Binds the handlers of a sent node whose parent is the last of the ancestors
+    fn bind_sent(&mut self, node: &mut Node, ancestors: &mut Vec<String>) {
+        if let Node::Element {
+            attrs,
+            handlers,
+            children,
+            ..
+        } = node
+        {
+            let element = id_of(attrs).map(String::from);
+            for handler in handlers.iter_mut() {
+                assign_id(handler);
+                let callback = callback(handler, &mut self.mismatched);
+                if let (Some(id), Some(callback)) = (&handler.id, callback) {
+                    let sent = Sent {
+                        callback: callback.clone(),
+                        element: element.clone(),
+                        ancestors: ancestors.clone(),
+                        seq: self.next_seq,
+                    };
+                    self.next_seq += 1;
+                    self.sent.insert(id.clone(), sent);
+                }
+            }
+            self.drop_oldest();
+
+            let len = ancestors.len();
+            ancestors.extend(element);
+            for child in children {
+                self.bind_sent(child, ancestors);
+            }
+            ancestors.truncate(len);
+        }
+    }
+
+    fn drop_oldest(&mut self) {
+        while self.sent.len() > MAX_SENT {
+            let oldest = self
+                .sent
+                .iter()
+                .min_by_key(|(_, sent)| sent.seq)
+                .map(|(id, _)| id.clone());
+            if let Some(id) = oldest {
+                self.sent.remove(&id);
+            }
+        }
+    }
+
+    /// Drops the closures of the elements the command removes
+    fn forget(&mut self, command: &Command) {
+        let target = match target_id(command) {
+            Some(target) => target,
+            None => return,
+        };
+        match command.command_type {
+            CommandType::RemoveElement | CommandType::ReplaceElement(_) => {
+                self.sent.retain(|_, sent| {
+                    sent.element.as_deref() != Some(target)
+                        && !sent.ancestors.iter().any(|id| id == target)
+                })
+            }
+            CommandType::ReplaceChildren(_)
+            | CommandType::ClearChildren
+            | CommandType::SetText(_) => self
+                .sent
+                .retain(|_, sent| !sent.ancestors.iter().any(|id| id == target)),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the id the command targets, commands targeting selectors can not be tracked
+fn target_id(command: &Command) -> Option<&str> {
+    match &command.target {
+        Some(Target::Id(id)) => Some(id),
+        _ => None,
+    }
+}
+
+/// Closures written for another App type, each is reported once
+#[derive(Default)]
+struct Mismatched {
+    reported: HashSet<String>,
+    errors: Vec<Error>,
+}
+
+/// Returns the handler's closure if it was written for App type T
+fn callback<'a, T: 'static>(
+    handler: &'a Handler,
+    mismatched: &mut Mismatched,
+) -> Option<&'a Callback<T>> {
+    let callback = handler.callback.as_ref()?.downcast_ref::<Callback<T>>();
+    if let (None, Some(id)) = (callback, &handler.id) {
+        if mismatched.reported.insert(id.clone()) {
+            let event_type = handler.event_kind.event_type().to_string();
+            mismatched.errors.push(Error::HandlerType(event_type));
+        }
+    }
+    callback
+}
+
+fn for_each_handler(node: &mut Node, f: &mut impl FnMut(&mut Handler)) {
+    if let Node::Element {
+        handlers, children, ..
+    } = node
+    {
+        handlers.iter_mut().for_each(&mut *f);
+        for child in children {
+            for_each_handler(child, f);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use super::{Handlers, MAX_SENT};
+use crate::builders::*;
+use crate::html::{EventKind, Node};
+use crate::{Command, Message};
+
+struct Counter {
+    count: u32,
+}
+
+fn click(handler: &str) -> Message {
+    serde_json::from_value(serde_json::json!({
+        "id": "button",
+        "event_type": "click",
+        "tag": "BUTTON",
+        "handler": handler,
+    }))
+    .unwrap()
+}
+
+fn handler_id(node: &Node) -> String {
+    match node {
+        Node::Element { handlers, .. } => handlers[0].id.clone().unwrap(),
+        _ => panic!("expected an element"),
+    }
+}
+
+fn button_adding(amount: u32) -> Node {
+    button()
+        .id("button")
+        .on(
+            EventKind::Onclick,
+            move |counter: &mut Counter, _: &Message| {
+                counter.count += amount;
+                vec![Command::none()]
+            },
+        )
+        .node()
+}
+
+#[test]
+fn bound_handlers_are_called_with_the_app() {
+    let mut handlers = Handlers::new();
+    let mut node = button_adding(2);
+    handlers.bind(&mut node);
+
+    let id = handler_id(&node);
+    let mut counter = Counter { count: 1 };
+    let commands = handlers.get(&id).unwrap()(&mut counter, &click(&id));
+    assert_eq!(counter.count, 3);
+    assert_eq!(commands.len(), 1);
+    assert!(node.into_html().contains(&format!(
+        r#"{{&quot;type&quot;:&quot;click&quot;,&quot;handler&quot;:&quot;{}&quot;}}"#,
+        id
+    )));
+}
+
+#[test]
+fn handlers_for_other_apps_are_reported_once() {
+    let mut handlers = Handlers::<()>::new();
+    let mut node = button_adding(1);
+    handlers.bind_view(&mut node);
+    assert!(handlers.get(&handler_id(&node)).is_none());
+    match handlers.take_errors().as_slice() {
+        [crate::Error::HandlerType(event_type)] => assert_eq!(event_type, "click"),
+        errors => panic!("expected a handler type error, got {:?}", errors),
+    }
+
+    handlers.bind_view(&mut node);
+    assert!(handlers.take_errors().is_empty());
+}
+
+#[test]
+fn binding_a_view_replaces_its_handlers() {
+    let mut handlers = Handlers::<Counter>::new();
+    let mut first = button_adding(1);
+    handlers.bind_view(&mut first);
+    let mut second = button_adding(1);
+    handlers.bind_view(&mut second);
+
+    assert!(handlers.get(&handler_id(&first)).is_none());
+    assert!(handlers.get(&handler_id(&second)).is_some());
+}
+
+#[test]
+fn commands_adding_nodes_bind_their_handlers() {
+    let mut handlers = Handlers::<Counter>::new();
+    let mut commands = vec![
+        Command::append_child_element("list", button_adding(1)),
+        Command::replace_children("other", vec![button_adding(2)]),
+    ];
+    handlers.bind_commands(&mut commands);
+
+    for command in &commands {
+        let node = match &command.command_type {
+            crate::CommandType::AppendChildElement(node) => node,
+            crate::CommandType::ReplaceChildren(nodes) => &nodes[0],
+            _ => unreachable!(),
+        };
+        assert!(handlers.get(&handler_id(node)).is_some());
+    }
+}
+
+#[test]
+fn closures_of_removed_elements_are_dropped() {
+    let mut handlers = Handlers::<Counter>::new();
+    let row = |id: &str| li().id(id).el(button_adding(1)).node();
+    let mut commands = vec![
+        Command::append_child_element("list", row("a")),
+        Command::append_child_element("list", row("b")),
+        Command::append_child_element("other", row("c")),
+    ];
+    handlers.bind_commands(&mut commands);
+    let ids: Vec<String> = commands
+        .iter()
+        .map(|command| match &command.command_type {
+            crate::CommandType::AppendChildElement(Node::Element { children, .. }) => {
+                handler_id(&children[0])
+            }
+            _ => unreachable!(),
+        })
+        .collect();
+
+    handlers.forget_removed(&[Command::remove_element("a")]);
+    assert!(handlers.get(&ids[0]).is_none());
+    assert!(handlers.get(&ids[1]).is_some());
+    handlers.bind_commands(&mut [Command::clear_children("list")]);
+    assert!(handlers.get(&ids[1]).is_none());
+    assert!(handlers.get(&ids[2]).is_some());
+}
+
+#[test]
+fn only_the_latest_sent_closures_are_kept() {
+    let mut handlers = Handlers::<Counter>::new();
+    let mut nodes: Vec<Node> = (0..=MAX_SENT).map(|_| button_adding(1)).collect();
+    nodes.iter_mut().for_each(|node| handlers.bind(node));
+
+    assert!(handlers.get(&handler_id(&nodes[0])).is_none());
+    assert!(handlers.get(&handler_id(&nodes[1])).is_some());
+    assert!(handlers.get(&handler_id(&nodes[MAX_SENT])).is_some());
+}
@@ -1,5 +1,19 @@
-use serde::Serialize;
+use serde::{Serialize, Serializer};
+use std::any::Any;
 use std::fmt;
+use std::sync::Arc;
+
+use crate::{Command, Message};
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+/// The script that connects the page to the server and applies commands
+pub(crate) const CLIENT_SCRIPT: &str = include_str!("html/client.js");
+
+/// The path the server serves the client script on, it is loaded rather than inlined
+/// so pages work under a Content-Security-Policy that forbids inline scripts
+pub(crate) const CLIENT_SCRIPT_PATH: &str = "/lowui/client.js";
 
 #[derive(Debug)]
 pub struct HtmlPage {
@@ -39,7 +53,9 @@ impl HtmlPage {
         self
     }
 
-    pub(crate) fn into_html(self) -> String {
+    /// Renders the page, the client script connects to the WebSocket endpoint
+    /// at the given path on the same origin the page was loaded from
+    pub(crate) fn into_html(self, ws_path: &str) -> String {
         let links = join_as_strings(self.links, "\n", |link| {
             let attrs = join_as_strings(link.attrs, " ", |attr| attr.to_string());
             format!("<link {}>", attrs)
@@ -52,139 +68,32 @@ impl HtmlPage {
 
         let nodes = join_as_strings(self.nodes, "\n", |node| -> String { node.into_html() });
 
-        format!("
+        format!(
+            "
         <html>
           <head>
             {metas}
             {links}
             <title>{title}</title>
-            <script type=\"text/javascript\">
-            
-            function sendMessage(obj) {{
-                if ( websocket != null )
-                {{
-                    var json = JSON.stringify(obj);
-                    websocket.send( json );
-                }}
-            }}
-
-            function receiveMessage(json) {{
-                var objs = JSON.parse(json);
-                objs.forEach((obj)=> {{
-                    if (obj.id) {{
-                        let command = obj.command_type;
-                        let element = document.getElementById(obj.id);
-                        if (command === 'RemoveElement') {{
-                            element.remove();
-                        }}
-                        else if (command.hasOwnProperty('AppendChildElement')) {{
-                            let newEl = makeElement(command.AppendChildElement);
-                            element.appendChild(newEl);
-                        }}
-                        else if (command.hasOwnProperty('InsertElementBefore')) {{
-                            let newEl = makeElement(command.InsertElementBefore);
-                            document.insertBefore(element, newEl);
-                        }}
-                        else if (command.hasOwnProperty('ReplaceElement')) {{
-                            let newEl = makeElement(command.ReplaceElement);
-                            element.parentNode.replaceChild(newEl, element);
-                        }}
-                        else if (command.hasOwnProperty('SetAttribute')) {{
-                            element.setAttribute(command.SetAttribute.name, command.SetAttribute.value);
-                        }}
-                        else if (command.hasOwnProperty('SetAttribute')) {{
-                            if (command.SetAttribute.hasOwnProperty('value')) {{
-                                element.setAttribute(command.SetAttribute.name, command.SetAttribute.value);
-                            }} else {{
-                                element.setAttribute(command.SetAttribute.name);
-                            }}
-                        }}
-                        else if (command.hasOwnProperty('RemoveAttribute')) {{
-                            element.removeAttribute(command.RemoveAttribute.name);
-                        }}
-                    }}
-                }});
-            }}
-
-            function makeElement(obj) {{
-                if (obj.Text) {{
-                    return document.createTextNode(obj.Text);
-                }}
-                
-                const element = obj.Element;
-                let newEl = window.document.createElement(element.tag);
-                element.attrs && element.attrs.forEach((a) => newEl.setAttribute(a.name, a.value));
-                element.children && element.children.forEach((c) => newEl.appendChild(makeElement(c)));
-                return newEl;
-            }}
-
-            var wsUri = \"ws://localhost:1234\";
-            var websocket = null;
-
-            function initWebSocket() {{
-                try {{
-                    if (typeof MozWebSocket == 'function')
-                        WebSocket = MozWebSocket;
-                    if ( websocket && websocket.readyState == 1 )
-                        websocket.close();
-                    websocket = new WebSocket( wsUri );
-                    websocket.onopen = function (evt) {{
-                    }};
-                    websocket.onclose = function (evt) {{
-                    }};
-                    websocket.onmessage = function (evt) {{
-                        receiveMessage(evt.data);
-                    }};
-                    websocket.onerror = function (evt) {{
-                    }};
-                }} catch (exception) {{
-                }}
-            }}
-
-            function stopWebSocket() {{
-                if (websocket) {{
-                    websocket.close();
-                }}
-            }}
-
-            window.onload = initWebSocket;
-            window.onclose = stopWebSocket;
-
-            function onevent() {{
-                if (event.target.type === 'radio' || event.target.type === 'checkbox') {{
-                    sendMessage({{
-                        type: event.target.type,
-                        id: event.target.id,
-                        name: event.target.nodeName,
-                        checked: event.target.checked 
-                    }});
-                }} else if (event.target.value !== '') {{
-                    sendMessage({{
-                        type: event.target.type,
-                        id: event.target.id,
-                        name: event.target.nodeName,
-                        value: event.target.value 
-                    }});
-                }} else {{
-                    sendMessage({{
-                        type: event.target.type,
-                        id: event.target.id,
-                        name: event.target.nodeName
-                    }});
-                }}
-            }}
-        </script>
+            <script src=\"{script_path}\" data-ws-path=\"{ws_path}\"></script>
           </head>
           <body>
             {nodes}
           </body>
         </html>
-        ", metas=metas, links=links, title=self.title, nodes=nodes)
+        ",
+            metas = metas,
+            links = links,
+            title = escape_text(&self.title),
+            script_path = CLIENT_SCRIPT_PATH,
+            ws_path = escape_attr(ws_path),
+            nodes = nodes
+        )
     }
 }
 
 /// An HTML attribute
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Attr {
     pub name: &'static str,
     pub value: Option<String>,
@@ -194,7 +103,7 @@ impl Attr {
     /// Returns a new Attr with key and value
     pub fn new<T: Into<String>>(name: &'static str, value: T) -> Self {
         Self {
-            name: name,
+            name,
             value: Some(value.into()),
         }
     }
@@ -202,7 +111,7 @@ impl Attr {
     /// Returns a new Attr with no value
     pub fn name_only(name: &'static str) -> Self {
         Self {
-            name: name,
+            name,
             value: None,
         }
     }
@@ -211,7 +120,7 @@ impl Attr {
 impl fmt::Display for Attr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(value) = &self.value {
-            write!(f, r#"{}="{}""#, self.name, value)
+            write!(f, r#"{}="{}""#, self.name, escape_attr(value))
         } else {
             write!(f, r#"{}"#, self.name)
         }
@@ -251,6 +160,12 @@ impl Meta {
     }
 }
 
+impl Default for Meta {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// A &lt;link rel="..."&gt; element
 #[derive(Debug)]
 pub struct Link {
@@ -307,7 +222,7 @@ impl Link {
 }
 
 /// An HTML element or text node
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Node {
     Element {
         /// The element type, or tag
@@ -318,27 +233,42 @@ pub enum Node {
         children: Vec<Node>,
         /// True if element is empty like &lt;br&gt;
         is_empty: bool,
+        /// Identifies the element among its siblings when a view is diffed, see `App::view`
+        #[serde(skip)]
+        key: Option<String>,
+        /// The events sent to the server, rendered as the data-lowui-events attribute
+        /// and sent to the client as the list of events the attribute declares
+        #[serde(rename = "events")]
+        handlers: Vec<Handler>,
     },
+    /// Text, escaped when rendered
     Text(String),
+    /// Markup that is rendered as is, see `Node::raw_html`
+    Raw(String),
 }
 
 impl Node {
     pub(crate) fn new_el(
         tag: &'static str,
-        events: Vec<EventKind>,
-        mut attrs: Vec<Attr>,
+        events: Vec<(EventKind, EventOptions)>,
+        attrs: Vec<Attr>,
         children: Vec<Node>,
         is_empty: bool,
+        key: Option<String>,
+        mut handlers: Vec<Handler>,
     ) -> Self {
-        for event_kind in events {
-            attrs.push(Attr::new(event_kind.to_str(), "onevent()"));
-        }
+        let events = events
+            .into_iter()
+            .map(|(event_kind, options)| Handler::update(event_kind, options));
+        handlers.splice(0..0, events);
 
         Self::Element {
-            tag: tag,
-            attrs: attrs,
-            children: children,
-            is_empty: is_empty,
+            tag,
+            attrs,
+            children,
+            is_empty,
+            key,
+            handlers,
         }
     }
 
@@ -346,14 +276,23 @@ impl Node {
         Node::Text(text.into())
     }
 
+    /// Returns a node that renders the given markup without escaping it,
+    /// only use this for trusted markup, never for user input
+    pub fn raw_html<T: Into<String>>(html: T) -> Self {
+        Node::Raw(html.into())
+    }
+
     pub(crate) fn into_html(self) -> String {
         match self {
             Self::Element {
                 tag,
-                attrs,
+                mut attrs,
                 children,
                 is_empty,
+                handlers,
+                ..
             } => {
+                attrs.extend(events_attr(&handlers));
                 let space;
                 let attr_txt;
                 if attrs.is_empty() {
@@ -370,7 +309,12 @@ impl Node {
                     let children = if children.is_empty() {
                         "".to_string()
                     } else {
-                        join_as_strings(children, "", |child| child.into_html())
+                        join_as_strings(children, "", |child| match child {
+                            Self::Text(text) if RAW_TEXT_TAGS.contains(&tag) => {
+                                raw_text(tag, &text)
+                            }
+                            child => child.into_html(),
+                        })
                     };
 
                     format!(
@@ -383,12 +327,158 @@ impl Node {
                 }
             }
 
-            Self::Text(t) => t.to_string(),
+            Self::Text(t) => escape_text(&t),
+
+            Self::Raw(html) => html,
         }
     }
 }
 
-#[derive(Debug)]
+/// An event the client sends to the server, to a closure bound with `on`
+/// or to `App::update` for events added with `event`
+#[derive(Clone)]
+pub struct Handler {
+    pub(crate) event_kind: EventKind,
+    pub(crate) options: EventOptions,
+    /// Given by the server when an element with a closure is sent to a client
+    pub(crate) id: Option<String>,
+    /// A Callback for the App type the closure was written for, None for `App::update`
+    pub(crate) callback: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+/// The type-erased form of a handler's closure
+pub(crate) type Callback<A> = Arc<dyn Fn(&mut A, &Message) -> Vec<Command> + Send + Sync>;
+
+/// The attribute listing an element's events, the client listens for them on the document
+pub(crate) const EVENTS_ATTR: &str = "data-lowui-events";
+
+impl Handler {
+    pub(crate) fn new<A, F>(event_kind: EventKind, options: EventOptions, handler: F) -> Self
+    where
+        A: 'static,
+        F: Fn(&mut A, &Message) -> Vec<Command> + Send + Sync + 'static,
+    {
+        let callback: Callback<A> = Arc::new(handler);
+        Self {
+            event_kind,
+            options,
+            id: None,
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    /// Returns a handler for an event passed to `App::update`
+    pub(crate) fn update(event_kind: EventKind, options: EventOptions) -> Self {
+        Self {
+            event_kind,
+            options,
+            id: None,
+            callback: None,
+        }
+    }
+}
+
+/// Returns the attribute that declares the events to the client, None if there are none
+pub(crate) fn events_attr(handlers: &[Handler]) -> Option<Attr> {
+    if handlers.is_empty() {
+        None
+    } else {
+        let events = serde_json::to_string(handlers).expect("handlers serialize");
+        Some(Attr::new(EVENTS_ATTR, events))
+    }
+}
+
+impl fmt::Debug for Handler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handler")
+            .field("event_kind", &self.event_kind)
+            .field("options", &self.options)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// A handler is declared to the client as its event type, its id and its options
+impl Serialize for Handler {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Declared<'a> {
+            #[serde(rename = "type")]
+            event_type: &'static str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            handler: Option<&'a str>,
+            #[serde(flatten)]
+            options: &'a EventOptions,
+        }
+
+        Declared {
+            event_type: self.event_kind.event_type(),
+            handler: self.id.as_deref(),
+            options: &self.options,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// How the client handles an event before sending it to the server,
+/// set with `event_with` and `on_with` on the element builders
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventOptions {
+    /// Calls preventDefault, so a link does not navigate for example
+    #[serde(skip_serializing_if = "is_false")]
+    pub prevent_default: bool,
+    /// Calls stopPropagation, so the event does not reach the element's ancestors
+    #[serde(skip_serializing_if = "is_false")]
+    pub stop_propagation: bool,
+    /// Sends the event once no more came for this many milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debounce_ms: Option<u32>,
+    /// Sends at most one event every this many milliseconds,
+    /// the last one is sent at the end of the interval
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle_ms: Option<u32>,
+    /// Sends only the first event
+    #[serde(skip_serializing_if = "is_false")]
+    pub once: bool,
+}
+
+impl EventOptions {
+    /// Returns options that send every event and leave the default action alone
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prevent_default(mut self) -> Self {
+        self.prevent_default = true;
+        self
+    }
+
+    pub fn stop_propagation(mut self) -> Self {
+        self.stop_propagation = true;
+        self
+    }
+
+    pub fn debounce_ms(mut self, ms: u32) -> Self {
+        self.debounce_ms = Some(ms);
+        self
+    }
+
+    pub fn throttle_ms(mut self, ms: u32) -> Self {
+        self.throttle_ms = Some(ms);
+        self
+    }
+
+    pub fn once(mut self) -> Self {
+        self.once = true;
+        self
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventKind {
     Onabort,
     Onauxclick,
@@ -454,6 +544,11 @@ pub enum EventKind {
 }
 
 impl EventKind {
+    /// Returns the DOM event type, such as click
+    pub(crate) fn event_type(&self) -> &'static str {
+        &self.to_str()["on".len()..]
+    }
+
     pub fn to_str(&self) -> &'static str {
         match self {
             EventKind::Onabort => "onabort",
@@ -521,6 +616,55 @@ impl EventKind {
     }
 }
 
+/// Elements whose text the browser does not parse for entities or tags
+const RAW_TEXT_TAGS: [&str; 2] = ["script", "style"];
+
+/// Returns the text of a raw text element such as style as is, except that anything
+/// that would close the element has its slash escaped, like <\/style, which CSS and
+/// JavaScript both allow in strings
+fn raw_text(tag: &str, text: &str) -> String {
+    let closing = format!("</{}", tag);
+    let mut escaped = String::with_capacity(text.len());
+    let mut rest = 0;
+    for (i, _) in text.to_ascii_lowercase().match_indices(&closing) {
+        escaped.push_str(&text[rest..=i]);
+        escaped.push('\\');
+        rest = i + 1;
+    }
+    escaped.push_str(&text[rest..]);
+    escaped
+}
+
+/// Escapes text for use as the content of an element
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes text for use as a double or single quoted attribute value
+fn escape_attr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 fn join_as_strings<T, F>(items: Vec<T>, delim: &'static str, fun: F) -> String
 where
     F: FnMut(T) -> String,
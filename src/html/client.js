@@ -0,0 +1,466 @@
+// Sends an Incoming value of the lowui crate
+function send(obj) {
+    if (websocket != null) {
+        websocket.send(JSON.stringify(obj));
+    }
+}
+
+function sendMessage(message) {
+    send({ Event: message });
+}
+
+// Appliers for each CommandType, keyed by variant name.
+// Unit variants are serialized as a bare string and carry no payload,
+// newtype variants as { Variant: payload }.
+const commandAppliers = {
+    RemoveElement: (element) => {
+        element.remove();
+    },
+    AppendChildElement: (element, node) => {
+        element.appendChild(makeElement(node));
+    },
+    InsertElementBefore: (element, node) => {
+        element.parentNode.insertBefore(makeElement(node), element);
+    },
+    ReplaceElement: (element, node) => {
+        element.parentNode.replaceChild(makeElement(node), element);
+    },
+    SetAttribute: (element, attr) => {
+        element.setAttribute(attr.name, attrValue(attr));
+        listenFor(element);
+    },
+    RemoveAttribute: (element, name) => {
+        element.removeAttribute(name);
+    },
+    SetProperty: (element, property) => {
+        element[property.name] = property.value;
+    },
+    MoveElementBefore: (element, beforeId) => {
+        const before = document.getElementById(beforeId);
+        if (!before) {
+            console.warn('lowui: no element with id ' + beforeId + ' to move before');
+            return;
+        }
+        before.parentNode.insertBefore(element, before);
+    },
+    MoveElementToEnd: (element) => {
+        element.parentNode.appendChild(element);
+    },
+    AddClass: (element, name) => {
+        element.classList.add(name);
+    },
+    RemoveClass: (element, name) => {
+        element.classList.remove(name);
+    },
+    ToggleClass: (element, toggle) => {
+        if (toggle.force === null) {
+            element.classList.toggle(toggle.name);
+        } else {
+            element.classList.toggle(toggle.name, toggle.force);
+        }
+    },
+    SetStyle: (element, style) => {
+        element.style.setProperty(style.property, style.value);
+    },
+    RemoveStyle: (element, property) => {
+        element.style.removeProperty(property);
+    },
+    SetText: (element, text) => {
+        element.textContent = text;
+    },
+    SetChildText: (element, child) => {
+        const node = element.childNodes[child.index];
+        if (!node || node.nodeType !== Node.TEXT_NODE) {
+            throw new Error('no text node at index ' + child.index);
+        }
+        node.data = child.text;
+    },
+    ReplaceChildren: (element, nodes) => {
+        element.replaceChildren(...nodes.map(makeElement));
+    },
+    ClearChildren: (element) => {
+        element.replaceChildren();
+    },
+};
+
+// A batch is an array of commands, or { seq, commands } if the server wants it acknowledged.
+// A command that fails is reported and the rest of the batch is still applied
+function receiveMessage(json) {
+    const batch = JSON.parse(json);
+    const numbered = !Array.isArray(batch);
+    const seq = numbered ? batch.seq : null;
+    for (const command of numbered ? batch.commands : batch) {
+        try {
+            applyCommand(command, seq);
+        } catch (exception) {
+            reportError('Exception', command, seq, String(exception));
+        }
+    }
+    if (numbered) {
+        send({ Ack: seq });
+    }
+}
+
+function applyCommand(command, seq) {
+    let type = command.command_type;
+    let payload = null;
+    if (typeof type !== 'string') {
+        [type, payload] = Object.entries(type)[0];
+    }
+    if (type === 'None') {
+        return;
+    }
+
+    const apply = commandAppliers[type];
+    if (!apply) {
+        console.warn('lowui: unknown command ' + type);
+        reportError('UnknownCommand', command, seq, 'unknown command ' + type);
+        return;
+    }
+
+    const elements = targetElements(command.target);
+    // A selector for every match may match nothing, such as when no item is completed
+    if (elements.length === 0 && !(command.target && 'SelectorAll' in command.target)) {
+        const message = 'no element ' + describeTarget(command.target);
+        console.warn('lowui: ' + message + ' for ' + type);
+        reportError('MissingTarget', command, seq, message);
+        return;
+    }
+    elements.forEach((element) => apply(element, payload));
+}
+
+// Sends a ClientError of the lowui crate
+function reportError(kind, command, seq, message) {
+    const type = command.command_type;
+    send({
+        Error: {
+            kind: kind,
+            command_type: typeof type === 'string' ? type : Object.keys(type)[0],
+            target: command.target || null,
+            message: message,
+            seq: seq,
+        },
+    });
+}
+
+// The elements a Target in the lowui crate gives
+function targetElements(target) {
+    if (!target) {
+        return [];
+    }
+    if ('Id' in target) {
+        const element = document.getElementById(target.Id);
+        return element ? [element] : [];
+    }
+    if ('Selector' in target) {
+        const element = document.querySelector(target.Selector);
+        return element ? [element] : [];
+    }
+    return Array.from(document.querySelectorAll(target.SelectorAll));
+}
+
+function describeTarget(target) {
+    if (!target) {
+        return 'targeted';
+    }
+    if ('Id' in target) {
+        return 'with id ' + target.Id;
+    }
+    return 'matching ' + (target.Selector || target.SelectorAll);
+}
+
+// Name only attributes are serialized with a null value
+function attrValue(attr) {
+    return attr.value === null ? '' : attr.value;
+}
+
+function makeElement(obj) {
+    if (obj.hasOwnProperty('Text')) {
+        return document.createTextNode(obj.Text);
+    }
+    if (obj.hasOwnProperty('Raw')) {
+        const template = document.createElement('template');
+        template.innerHTML = obj.Raw;
+        return template.content;
+    }
+
+    const element = obj.Element;
+    let newEl = document.createElement(element.tag);
+    element.attrs.forEach((a) => newEl.setAttribute(a.name, attrValue(a)));
+    if (element.events.length > 0) {
+        newEl.setAttribute('data-lowui-events', JSON.stringify(element.events));
+    }
+    listenFor(newEl);
+    element.children.forEach((c) => newEl.appendChild(makeElement(c)));
+    return newEl;
+}
+
+// The page passes the WebSocket path to the script tag, which is only current while it runs
+var wsPath = document.currentScript.getAttribute('data-ws-path');
+var wsScheme = window.location.protocol === 'https:' ? 'wss://' : 'ws://';
+var wsUri = wsScheme + window.location.host + wsPath;
+var websocket = null;
+
+function initWebSocket() {
+    try {
+        if (typeof MozWebSocket == 'function')
+            WebSocket = MozWebSocket;
+        if (websocket && websocket.readyState == 1)
+            websocket.close();
+        websocket = new WebSocket(wsUri);
+        websocket.onopen = function (evt) {
+        };
+        websocket.onclose = function (evt) {
+        };
+        websocket.onmessage = function (evt) {
+            receiveMessage(evt.data);
+        };
+        websocket.onerror = function (evt) {
+        };
+    } catch (exception) {
+    }
+}
+
+function stopWebSocket() {
+    if (websocket) {
+        websocket.close();
+    }
+}
+
+window.onload = function () {
+    document.querySelectorAll('[data-lowui-events]').forEach(listenFor);
+    initWebSocket();
+};
+window.onclose = stopWebSocket;
+
+// The event types there is a listener for on the document
+const listening = new Set();
+
+// The events an element declares, each has the type, the id of the closure bound to it
+// if there is one, and the EventOptions of the lowui crate with the defaults left out
+function declaredEvents(element) {
+    const json = element.getAttribute('data-lowui-events');
+    return json ? JSON.parse(json) : [];
+}
+
+// Listens on the document for the events the element declares,
+// and on the element itself for the ones it stops
+function listenFor(element) {
+    for (const declared of declaredEvents(element)) {
+        if (!listening.has(declared.type)) {
+            listening.add(declared.type);
+            // Events that do not bubble, such as focus and mouseenter,
+            // only reach the document while capturing
+            document.addEventListener(declared.type, (event) => event.bubbles || onevent(event), true);
+            document.addEventListener(declared.type, (event) => event.bubbles && onevent(event));
+        }
+        if (declared.stop_propagation) {
+            stopAt(element, declared.type);
+        }
+    }
+}
+
+// The event would only reach the document after the listeners of the element's ancestors ran,
+// so it is sent and stopped by a listener on the element while it still declares stopping it
+function stopAt(element, type) {
+    element.lowuiStopped = element.lowuiStopped || new Set();
+    if (element.lowuiStopped.has(type)) {
+        return;
+    }
+    element.lowuiStopped.add(type);
+    element.addEventListener(type, (event) => {
+        const stops = declaredEvents(element).some((d) => d.type === type && d.stop_propagation);
+        if (event.bubbles && stops) {
+            onevent(event);
+            event.stopPropagation();
+        }
+    });
+}
+
+// Sends the event for the target and, if it bubbles, for its ancestors that declare it
+function onevent(event) {
+    let element = event.target;
+    while (element && element.getAttribute) {
+        const declared = declaredEvents(element).filter((d) => d.type === event.type);
+        declared.forEach((d) => sendEvent(event, element, d));
+        if (!event.bubbles || declared.some((d) => d.stop_propagation)) {
+            return;
+        }
+        element = element.parentNode;
+    }
+}
+
+function sendEvent(event, element, declared) {
+    if (event.type === 'submit' || declared.prevent_default) {
+        // A form is sent to the server instead of navigating away
+        event.preventDefault();
+    }
+    const message = eventMessage(event, element, declared.handler);
+    const state = eventState(element, declared);
+    if (declared.once) {
+        if (state.sent) {
+            return;
+        }
+        state.sent = true;
+    }
+    if (declared.debounce_ms) {
+        clearTimeout(state.timer);
+        state.timer = setTimeout(() => sendMessage(message), declared.debounce_ms);
+    } else if (declared.throttle_ms) {
+        throttle(state, message, declared.throttle_ms);
+    } else {
+        sendMessage(message);
+    }
+}
+
+// The timers and sent events of an element's event, for debounce, throttle and once
+function eventState(element, declared) {
+    const key = declared.type + ' ' + (declared.handler || '');
+    element.lowuiEvents = element.lowuiEvents || {};
+    element.lowuiEvents[key] = element.lowuiEvents[key] || { sent: false, timer: null, last: -Infinity };
+    return element.lowuiEvents[key];
+}
+
+// Sends the message unless one was sent less than interval ms ago,
+// then the latest message is sent when the interval ends
+function throttle(state, message, interval) {
+    state.pending = message;
+    if (state.timer !== null) {
+        return;
+    }
+    const send = () => {
+        state.timer = null;
+        state.last = Date.now();
+        sendMessage(state.pending);
+    };
+    const wait = state.last + interval - Date.now();
+    if (wait <= 0) {
+        send();
+    } else {
+        state.timer = setTimeout(send, wait);
+    }
+}
+
+// Collects a Message for the element that declared the event,
+// see the Message type in the lowui crate
+function eventMessage(event, element, handler) {
+    const target = event.target;
+    const message = {
+        id: element.id || '',
+        event_type: event.type,
+        tag: element.nodeName,
+        data: dataAttributes(element),
+        event: eventDetails(event),
+    };
+    if (typeof target.type === 'string' && target.type !== '') {
+        message.target_type = target.type;
+    }
+    if (target.type === 'radio' || target.type === 'checkbox') {
+        message.checked = target.checked;
+    } else if (typeof target.value === 'string') {
+        message.value = target.value;
+    }
+    if (target.nodeName === 'FORM') {
+        message.fields = formFields(target);
+    }
+    if (handler) {
+        message.handler = handler;
+    }
+    return message;
+}
+
+// Controls whose value is not part of a form's fields
+const UNSENT_CONTROL_TYPES = ['submit', 'button', 'reset', 'image', 'file'];
+
+// The value of every named control in a form, a FieldValue in the lowui crate for each name
+function formFields(form) {
+    const controls = Array.from(form.elements).filter(
+        (control) => control.name && !control.disabled && !UNSENT_CONTROL_TYPES.includes(control.type)
+    );
+    const checkboxes = {};
+    controls
+        .filter((control) => control.type === 'checkbox')
+        .forEach((control) => checkboxes[control.name] = (checkboxes[control.name] || 0) + 1);
+
+    const fields = {};
+    for (const control of controls) {
+        const name = control.name;
+        if (control.type === 'checkbox' && checkboxes[name] > 1) {
+            fields[name] = fields[name] || { Many: [] };
+            if (control.checked) {
+                fields[name].Many.push(control.value);
+            }
+        } else if (control.type === 'checkbox') {
+            fields[name] = { Checked: control.checked };
+        } else if (control.type === 'radio') {
+            if (control.checked) {
+                fields[name] = { Text: control.value };
+            } else if (!(name in fields)) {
+                fields[name] = 'Unset';
+            }
+        } else if (control.type === 'select-multiple') {
+            fields[name] = { Many: Array.from(control.selectedOptions).map((option) => option.value) };
+        } else {
+            fields[name] = { Text: control.value };
+        }
+    }
+    return fields;
+}
+
+function dataAttributes(element) {
+    const data = {};
+    for (const attr of Array.from(element.attributes || [])) {
+        if (attr.name.startsWith('data-') && attr.name !== 'data-lowui-events') {
+            data[attr.name.slice('data-'.length)] = attr.value;
+        }
+    }
+    return data;
+}
+
+function modifiers(event) {
+    return {
+        alt: event.altKey,
+        ctrl: event.ctrlKey,
+        meta: event.metaKey,
+        shift: event.shiftKey,
+    };
+}
+
+function mouseDetails(event) {
+    return {
+        client_x: event.clientX,
+        client_y: event.clientY,
+        offset_x: event.offsetX,
+        offset_y: event.offsetY,
+        button: event.button,
+        buttons: event.buttons,
+        modifiers: modifiers(event),
+    };
+}
+
+// The details of an event, an Event in the lowui crate
+function eventDetails(event) {
+    if ('deltaY' in event) {
+        return {
+            kind: 'Wheel',
+            delta_x: event.deltaX,
+            delta_y: event.deltaY,
+            delta_z: event.deltaZ,
+            delta_mode: event.deltaMode,
+            mouse: mouseDetails(event),
+        };
+    }
+    if ('key' in event) {
+        return {
+            kind: 'Keyboard',
+            key: event.key,
+            code: event.code,
+            repeat: event.repeat,
+            modifiers: modifiers(event),
+        };
+    }
+    if ('clientX' in event) {
+        return Object.assign({ kind: 'Mouse' }, mouseDetails(event));
+    }
+    return { kind: 'Other' };
+}
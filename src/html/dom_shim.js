@@ -0,0 +1,376 @@
+// A minimal stand-in for the browser DOM, just enough to run the client script in tests
+
+const VOID_TAGS = ['area', 'br', 'col', 'embed', 'hr', 'img', 'input', 'param', 'source', 'track', 'wbr'];
+
+var Node = { ELEMENT_NODE: 1, TEXT_NODE: 3 };
+
+class ShimText {
+    constructor(text) {
+        this.nodeType = Node.TEXT_NODE;
+        this.data = text;
+        this.parentNode = null;
+    }
+
+    remove() {
+        if (this.parentNode) {
+            this.parentNode.removeChild(this);
+        }
+    }
+
+    toHtml() {
+        return this.data.replace(/&/g, '&amp;').replace(/</g, '&lt;').replace(/>/g, '&gt;');
+    }
+}
+
+// Parsed markup, kept as a string
+class ShimRaw extends ShimText {
+    toHtml() {
+        return this.data;
+    }
+}
+
+class ShimElement {
+    constructor(tag) {
+        this.nodeType = Node.ELEMENT_NODE;
+        this.tagName = tag.toUpperCase();
+        this.nodeName = this.tagName;
+        this.attributes = [];
+        this.childNodes = [];
+        this.parentNode = null;
+        this.listeners = [];
+    }
+
+    addEventListener(type, listener, capture) {
+        this.listeners.push({ type: type, listener: listener, capture: capture === true });
+    }
+
+    get id() {
+        return this.getAttribute('id') || '';
+    }
+
+    // Reflects the type attribute like the DOM does for inputs and buttons
+    get type() {
+        if (this.tagName === 'INPUT') {
+            return this.getAttribute('type') || 'text';
+        }
+        if (this.tagName === 'BUTTON') {
+            return this.getAttribute('type') || 'submit';
+        }
+        if (this.tagName === 'SELECT') {
+            return this.hasAttribute('multiple') ? 'select-multiple' : 'select-one';
+        }
+        if (this.tagName === 'TEXTAREA') {
+            return 'textarea';
+        }
+        return undefined;
+    }
+
+    // Changes the class attribute, like the DOM
+    get classList() {
+        const element = this;
+        const names = () => (element.getAttribute('class') || '').split(' ').filter((n) => n);
+        const set = (list) => element.setAttribute('class', list.join(' '));
+        return {
+            contains: (name) => names().includes(name),
+            add: (name) => set(names().filter((n) => n !== name).concat([name])),
+            remove: (name) => set(names().filter((n) => n !== name)),
+            toggle: (name, force) => {
+                const add = force === undefined ? !names().includes(name) : force;
+                set(add ? names().filter((n) => n !== name).concat([name]) : names().filter((n) => n !== name));
+                return add;
+            },
+        };
+    }
+
+    // Changes the style attribute, like the DOM
+    get style() {
+        const element = this;
+        const properties = () =>
+            (element.getAttribute('style') || '')
+                .split(';')
+                .map((p) => p.split(':').map((s) => s.trim()))
+                .filter((p) => p[0]);
+        const set = (list) => element.setAttribute('style', list.map((p) => p[0] + ': ' + p[1] + ';').join(' '));
+        return {
+            setProperty: (property, value) => {
+                const list = properties();
+                const existing = list.find((p) => p[0] === property);
+                if (existing) {
+                    existing[1] = value;
+                } else {
+                    list.push([property, value]);
+                }
+                set(list);
+            },
+            removeProperty: (property) => set(properties().filter((p) => p[0] !== property)),
+        };
+    }
+
+    get name() {
+        return this.getAttribute('name') || '';
+    }
+
+    // The attribute until the property is set, like the DOM
+    get disabled() {
+        return this.disabledProperty === undefined ? this.hasAttribute('disabled') : this.disabledProperty;
+    }
+
+    set disabled(disabled) {
+        this.disabledProperty = disabled;
+    }
+
+    // The controls in a form, values and checked state are set as plain properties in tests
+    get elements() {
+        return descendants(this).filter((e) => CONTROL_TAGS.includes(e.tagName));
+    }
+
+    // The options of a select whose selected property was set
+    get selectedOptions() {
+        return descendants(this).filter((e) => e.tagName === 'OPTION' && e.selected);
+    }
+
+    getAttribute(name) {
+        const attr = this.attributes.find((a) => a.name === name);
+        return attr ? attr.value : null;
+    }
+
+    hasAttribute(name) {
+        return this.attributes.some((a) => a.name === name);
+    }
+
+    setAttribute(name, value) {
+        if (value === undefined) {
+            throw new TypeError('setAttribute requires 2 arguments');
+        }
+        const attr = this.attributes.find((a) => a.name === name);
+        if (attr) {
+            attr.value = String(value);
+        } else {
+            this.attributes.push({ name: name, value: String(value) });
+        }
+    }
+
+    removeAttribute(name) {
+        this.attributes = this.attributes.filter((a) => a.name !== name);
+    }
+
+    appendChild(child) {
+        child.remove();
+        child.parentNode = this;
+        this.childNodes.push(child);
+        return child;
+    }
+
+    insertBefore(child, reference) {
+        if (reference === null) {
+            return this.appendChild(child);
+        }
+        const index = this.childNodes.indexOf(reference);
+        if (index < 0) {
+            throw new Error('insertBefore: reference is not a child');
+        }
+        child.remove();
+        child.parentNode = this;
+        this.childNodes.splice(this.childNodes.indexOf(reference), 0, child);
+        return child;
+    }
+
+    replaceChild(child, old) {
+        const index = this.childNodes.indexOf(old);
+        if (index < 0) {
+            throw new Error('replaceChild: old is not a child');
+        }
+        child.remove();
+        this.childNodes[this.childNodes.indexOf(old)] = child;
+        child.parentNode = this;
+        old.parentNode = null;
+        return old;
+    }
+
+    removeChild(child) {
+        this.childNodes = this.childNodes.filter((c) => c !== child);
+        child.parentNode = null;
+        return child;
+    }
+
+    remove() {
+        if (this.parentNode) {
+            this.parentNode.removeChild(this);
+        }
+    }
+
+    replaceChildren(...children) {
+        this.childNodes.forEach((child) => (child.parentNode = null));
+        this.childNodes = [];
+        children.forEach((child) => this.appendChild(child));
+    }
+
+    set textContent(text) {
+        this.replaceChildren(new ShimText(text));
+    }
+
+    get innerHTML() {
+        return this.childNodes.map((c) => c.toHtml()).join('');
+    }
+
+    set innerHTML(html) {
+        this.childNodes = [];
+        this.appendChild(new ShimRaw(html));
+    }
+
+    // Only used on template elements, the parsed markup
+    get content() {
+        return this.childNodes[0];
+    }
+
+    toHtml() {
+        const tag = this.tagName.toLowerCase();
+        const attrs = this.attributes.map((a) => ' ' + a.name + '="' + a.value + '"').join('');
+        if (VOID_TAGS.includes(tag)) {
+            return '<' + tag + attrs + '>';
+        }
+        return '<' + tag + attrs + '>' + this.innerHTML + '</' + tag + '>';
+    }
+}
+
+const CONTROL_TAGS = ['BUTTON', 'INPUT', 'SELECT', 'TEXTAREA'];
+
+function descendants(element) {
+    return element.childNodes
+        .filter((child) => child instanceof ShimElement)
+        .flatMap((child) => [child].concat(descendants(child)));
+}
+
+function findById(element, id) {
+    if (element instanceof ShimText) {
+        return null;
+    }
+    if (element.id === id) {
+        return element;
+    }
+    for (const child of element.childNodes) {
+        const found = findById(child, id);
+        if (found) {
+            return found;
+        }
+    }
+    return null;
+}
+
+// Supports compound selectors of a tag, #id, .class and [attribute], and descendant combinators
+function matchesSelector(element, selector) {
+    const compounds = selector.trim().split(/\s+/);
+    if (!matchesCompound(element, compounds.pop())) {
+        return false;
+    }
+    let ancestor = element.parentNode;
+    while (compounds.length > 0) {
+        if (!(ancestor instanceof ShimElement)) {
+            return false;
+        }
+        if (matchesCompound(ancestor, compounds[compounds.length - 1])) {
+            compounds.pop();
+        }
+        ancestor = ancestor.parentNode;
+    }
+    return true;
+}
+
+function matchesCompound(element, compound) {
+    const parts = /([#.]?)([\w-]+)|\[([\w-]+)\]/g;
+    let part;
+    while ((part = parts.exec(compound)) !== null) {
+        const [, prefix, name, attribute] = part;
+        const matches =
+            attribute !== undefined ? element.hasAttribute(attribute)
+            : prefix === '#' ? element.id === name
+            : prefix === '.' ? element.classList.contains(name)
+            : element.tagName === name.toUpperCase();
+        if (!matches) {
+            return false;
+        }
+    }
+    return true;
+}
+
+function currentScript() {
+    const script = new ShimElement('script');
+    script.setAttribute('data-ws-path', '/ws');
+    return script;
+}
+
+var document = {
+    body: new ShimElement('body'),
+    currentScript: currentScript(),
+    listeners: [],
+    createElement: (tag) => new ShimElement(tag),
+    createTextNode: (text) => new ShimText(text),
+    getElementById: (id) => findById(document.body, id),
+    querySelector: (selector) => document.querySelectorAll(selector)[0] || null,
+    querySelectorAll: (selector) => descendants(document.body).filter((e) => matchesSelector(e, selector)),
+    addEventListener: (type, listener, capture) => {
+        document.listeners.push({ type: type, listener: listener, capture: capture === true });
+    },
+};
+
+// Dispatches the event as if it happened on the target: to the document's capturing listeners,
+// then to the target and, if it bubbles, its ancestors and the document until it is stopped,
+// returns the calls made on the event
+function dispatch(target, event) {
+    const calls = [];
+    let stopped = false;
+    event.target = target;
+    event.bubbles = event.bubbles !== false;
+    event.preventDefault = () => calls.push('preventDefault');
+    event.stopPropagation = () => {
+        stopped = true;
+        calls.push('stopPropagation');
+    };
+    const run = (node, capture) =>
+        node.listeners
+            .filter((l) => l.type === event.type && l.capture === capture)
+            .forEach((l) => l.listener(event));
+    run(document, true);
+    for (let node = target; node && !stopped; node = event.bubbles ? node.parentNode : null) {
+        run(node, false);
+    }
+    if (event.bubbles && !stopped) {
+        run(document, false);
+    }
+    return calls;
+}
+
+var warnings = [];
+var console = {
+    warn: (message) => warnings.push(message),
+};
+
+// Timers run when tests advance the clock with tick
+var clock = 0;
+var timers = [];
+Date.now = () => clock;
+
+function setTimeout(callback, ms) {
+    const timer = { callback: callback, at: clock + ms };
+    timers.push(timer);
+    return timer;
+}
+
+function clearTimeout(timer) {
+    timers = timers.filter((t) => t !== timer);
+}
+
+function tick(ms) {
+    clock += ms;
+    let due;
+    while ((due = timers.filter((t) => t.at <= clock)).length > 0) {
+        due.sort((a, b) => a.at - b.at);
+        clearTimeout(due[0]);
+        due[0].callback();
+    }
+}
+
+var window = {
+    document: document,
+    location: { protocol: 'http:', host: 'localhost:8000' },
+};
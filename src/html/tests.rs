@@ -0,0 +1,843 @@
+use boa_engine::{Context, Source};
+use std::collections::HashMap;
+
+use super::{EventKind, EventOptions, HtmlPage, Link, Node, CLIENT_SCRIPT};
+use crate::builders::*;
+use crate::{Command, FieldValue, Target};
+
+const DOM_SHIM: &str = include_str!("dom_shim.js");
+
+/// Runs the client script against a fake DOM
+pub(crate) struct Client {
+    context: Context,
+}
+
+impl Client {
+    /// Returns a client whose body contains the given nodes
+    pub(crate) fn new(nodes: Vec<Node>) -> Self {
+        let mut client = Self {
+            context: Context::default(),
+        };
+        client.eval(DOM_SHIM);
+        client.eval(CLIENT_SCRIPT);
+        for node in nodes {
+            let json = serde_json::to_string(&node).unwrap();
+            client.eval(&format!(
+                "document.body.appendChild(makeElement({}));",
+                json
+            ));
+        }
+        client
+    }
+
+    pub(crate) fn eval(&mut self, script: &str) -> String {
+        let value = self
+            .context
+            .eval(Source::from_bytes(script))
+            .unwrap_or_else(|err| panic!("script failed: {}", err));
+        value
+            .to_string(&mut self.context)
+            .unwrap()
+            .to_std_string_escaped()
+    }
+
+    /// Sends commands to the client the way the server does
+    pub(crate) fn receive(&mut self, commands: Vec<Command>) {
+        let json = serde_json::to_string(&commands).unwrap();
+        let literal = serde_json::to_string(&json).unwrap();
+        self.eval(&format!("receiveMessage({});", literal));
+    }
+
+    pub(crate) fn body(&mut self) -> String {
+        self.eval("document.body.innerHTML")
+    }
+
+    pub(crate) fn warnings(&mut self) -> String {
+        self.eval("warnings.join('\\n')")
+    }
+}
+
+fn list() -> Vec<Node> {
+    vec![ul()
+        .id("list")
+        .el(li().id("a").text("A"))
+        .el(li().id("b").text("B"))
+        .node()]
+}
+
+#[test]
+fn append_child_element_appends_to_target() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::append_child_element(
+        "list",
+        li().id("c").text("C").node(),
+    )]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="a">A</li><li id="b">B</li><li id="c">C</li></ul>"#
+    );
+}
+
+#[test]
+fn insert_element_before_inserts_into_targets_parent() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::insert_element_before(
+        "b",
+        li().id("c").text("C").node(),
+    )]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="a">A</li><li id="c">C</li><li id="b">B</li></ul>"#
+    );
+}
+
+#[test]
+fn replace_element_replaces_target() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::replace_element(
+        "a",
+        li().id("c").text("C").node(),
+    )]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="c">C</li><li id="b">B</li></ul>"#
+    );
+}
+
+#[test]
+fn remove_element_removes_target() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::remove_element("a")]);
+    assert_eq!(client.body(), r#"<ul id="list"><li id="b">B</li></ul>"#);
+}
+
+#[test]
+fn move_element_before_moves_target() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::move_element_before("b", "a")]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="b">B</li><li id="a">A</li></ul>"#
+    );
+}
+
+#[test]
+fn move_element_to_end_moves_target() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::move_element_to_end("a")]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="b">B</li><li id="a">A</li></ul>"#
+    );
+}
+
+#[test]
+fn set_attribute_sets_value() {
+    let mut client = Client::new(list());
+    client.receive(vec![
+        Command::set_attribute("a", "title", "first"),
+        Command::set_attribute("a", "title", "changed"),
+    ]);
+    assert_eq!(
+        client.body(),
+        r#"<ul id="list"><li id="a" title="changed">A</li><li id="b">B</li></ul>"#
+    );
+}
+
+#[test]
+fn set_name_only_attribute_sets_empty_value() {
+    let mut client = Client::new(vec![input().id("check").node()]);
+    client.receive(vec![Command::set_name_only_attribute("check", "checked")]);
+    assert_eq!(client.body(), r#"<input id="check" checked="">"#);
+}
+
+#[test]
+fn remove_attribute_removes_by_name() {
+    let mut client = Client::new(vec![input().id("check").checked().node()]);
+    client.receive(vec![Command::remove_attribute("check", "checked")]);
+    assert_eq!(client.body(), r#"<input id="check">"#);
+}
+
+#[test]
+fn set_property_sets_properties_not_attributes() {
+    let mut client = Client::new(vec![
+        input().id("text").value("typed").node(),
+        input().id("check").r#type("checkbox").node(),
+        select().id("pick").node(),
+    ]);
+    client.receive(vec![
+        Command::set_value("text", ""),
+        Command::set_checked("check", true),
+        Command::set_disabled("check", true),
+        Command::set_selected_index("pick", -1),
+        Command::set_property("text", "placeholder", "Name"),
+    ]);
+    assert_eq!(
+        client.eval(
+            "var text = document.getElementById('text');
+            var check = document.getElementById('check');
+            JSON.stringify([text.value, text.placeholder, check.checked, check.disabled,
+                document.getElementById('pick').selectedIndex])"
+        ),
+        r#"["","Name",true,true,-1]"#
+    );
+    assert_eq!(
+        client.body(),
+        concat!(
+            r#"<input id="text" value="typed">"#,
+            r#"<input id="check" type="checkbox">"#,
+            r#"<select id="pick"></select>"#
+        )
+    );
+}
+
+#[test]
+fn class_commands_change_the_class_list() {
+    let mut client = Client::new(vec![li().id("item").class("todo editing").node()]);
+    client.receive(vec![
+        Command::add_class("item", "completed"),
+        Command::remove_class("item", "editing"),
+        Command::toggle_class("item", "todo", None),
+        Command::toggle_class("item", "hidden", Some(false)),
+        Command::toggle_class("item", "new", Some(true)),
+        Command::toggle_class("item", "new", Some(true)),
+    ]);
+    assert_eq!(
+        client.body(),
+        r#"<li id="item" class="completed new"></li>"#
+    );
+}
+
+#[test]
+fn style_commands_change_the_inline_style() {
+    let mut client = Client::new(vec![div().id("gauge").style("width: 10%").node()]);
+    client.receive(vec![
+        Command::set_style("gauge", "color", "red"),
+        Command::set_style("gauge", "width", "50%"),
+        Command::remove_style("gauge", "color"),
+    ]);
+    assert_eq!(
+        client.body(),
+        r#"<div id="gauge" style="width: 50%;"></div>"#
+    );
+}
+
+#[test]
+fn children_commands_replace_the_children() {
+    let mut client = Client::new(vec![ul()
+        .id("list")
+        .el(li().id("a").text("A").el(b().text("old")))
+        .el(li().id("b").text("B"))
+        .el(li().id("c").text("C").el(i().text("old")))
+        .node()]);
+    client.receive(vec![
+        Command::set_text("a", "1 < 2"),
+        Command::replace_children(
+            "b",
+            vec![b().text("bold").node(), Node::raw_html("<i>x</i>")],
+        ),
+        Command::clear_children("c"),
+    ]);
+    assert_eq!(
+        client.body(),
+        concat!(
+            r#"<ul id="list"><li id="a">1 &lt; 2</li>"#,
+            r#"<li id="b"><b>bold</b><i>x</i></li><li id="c"></li></ul>"#
+        )
+    );
+}
+
+#[test]
+fn make_element_builds_nested_nodes_and_name_only_attrs() {
+    let mut client = Client::new(vec![div().id("root").node()]);
+    client.receive(vec![Command::append_child_element(
+        "root",
+        p().text("Hello ")
+            .el(input().r#type("checkbox").checked())
+            .text("")
+            .node(),
+    )]);
+    assert_eq!(
+        client.body(),
+        r#"<div id="root"><p>Hello <input type="checkbox" checked=""></p></div>"#
+    );
+    assert_eq!(
+        client.eval("document.getElementById('root').childNodes[0].childNodes.length"),
+        "3"
+    );
+}
+
+#[test]
+fn none_is_ignored() {
+    let mut client = Client::new(list());
+    client.receive(vec![Command::none(), Command::remove_element("b")]);
+    assert_eq!(client.body(), r#"<ul id="list"><li id="a">A</li></ul>"#);
+}
+
+#[test]
+fn missing_target_does_not_stop_the_batch() {
+    let mut client = Client::new(list());
+    client.receive(vec![
+        Command::remove_element("missing"),
+        Command::remove_element("a"),
+    ]);
+    assert_eq!(client.body(), r#"<ul id="list"><li id="b">B</li></ul>"#);
+    assert!(client.warnings().contains("missing"));
+}
+
+#[test]
+fn selector_all_targets_every_match() {
+    let mut client = Client::new(vec![ul()
+        .id("todos")
+        .el(li().class("completed").text("a"))
+        .el(li().text("b"))
+        .el(li().class("todo completed").text("c"))
+        .node()]);
+    client.receive(vec![Command::remove_element(Target::selector_all(
+        "#todos li.completed",
+    ))]);
+    assert_eq!(client.body(), r#"<ul id="todos"><li>b</li></ul>"#);
+}
+
+#[test]
+fn selector_targets_the_first_match() {
+    let mut client = Client::new(vec![div()
+        .class("setting")
+        .el(input().id("a"))
+        .el(input().id("b"))
+        .node()]);
+    client.receive(vec![
+        Command::set_name_only_attribute(Target::selector(".setting input"), "disabled"),
+        Command::remove_element(Target::selector("p.none")),
+    ]);
+    assert_eq!(
+        client.body(),
+        r#"<div class="setting"><input id="a" disabled=""><input id="b"></div>"#
+    );
+    assert!(client.warnings().contains("matching p.none"));
+}
+
+/// Has the client receive the JSON, returns what it sends back
+fn replies(client: &mut Client, json: &str) -> Vec<crate::message::Incoming> {
+    let sent = client.eval(&format!(
+        "var sent = [];
+        send = (obj) => sent.push(obj);
+        receiveMessage({});
+        JSON.stringify(sent)",
+        serde_json::to_string(json).unwrap()
+    ));
+    serde_json::from_str(&sent).unwrap_or_else(|err| panic!("{}: {}", err, sent))
+}
+
+#[test]
+fn failed_commands_are_reported_and_the_batch_goes_on() {
+    use crate::message::Incoming;
+    use crate::ClientErrorKind;
+
+    let mut client = Client::new(list());
+    client.eval("commandAppliers.SetText = () => { throw new Error('boom'); };");
+    let commands = vec![
+        Command::set_text("a", "x"),
+        Command::remove_element("missing"),
+        Command::remove_element("a"),
+    ];
+    let mut json = serde_json::to_value(&commands).unwrap();
+    json[1]["command_type"] = "Explode".into();
+    let sent = replies(&mut client, &json.to_string());
+
+    assert_eq!(client.body(), r#"<ul id="list"><li id="b">B</li></ul>"#);
+    let errors: Vec<_> = sent
+        .into_iter()
+        .map(|incoming| match incoming {
+            Incoming::Error(error) => error,
+            incoming => panic!("expected an error, got {:?}", incoming),
+        })
+        .collect();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].kind, ClientErrorKind::Exception);
+    assert_eq!(errors[0].command_type, "SetText");
+    assert!(errors[0].message.contains("boom"));
+    assert_eq!(errors[1].kind, ClientErrorKind::UnknownCommand);
+    assert_eq!(errors[1].target, Some(Target::Id("missing".to_string())));
+    assert_eq!(errors[1].seq, None);
+}
+
+#[test]
+fn selector_all_without_matches_is_not_an_error() {
+    let mut client = Client::new(list());
+    let commands = vec![Command::remove_element(Target::selector_all(
+        "li.completed",
+    ))];
+    let sent = replies(&mut client, &serde_json::to_string(&commands).unwrap());
+    assert!(sent.is_empty(), "expected no errors, got {:?}", sent);
+    assert_eq!(client.warnings(), "");
+}
+
+#[test]
+fn numbered_batches_are_acknowledged() {
+    use crate::message::Incoming;
+    use crate::ClientErrorKind;
+
+    let mut client = Client::new(list());
+    let commands = vec![
+        Command::remove_element("missing"),
+        Command::remove_element("a"),
+    ];
+    let json = format!(
+        r#"{{"seq":7,"commands":{}}}"#,
+        serde_json::to_string(&commands).unwrap()
+    );
+    let sent = replies(&mut client, &json);
+
+    assert_eq!(client.body(), r#"<ul id="list"><li id="b">B</li></ul>"#);
+    match &sent[..] {
+        [Incoming::Error(error), Incoming::Ack(7)] => {
+            assert_eq!(error.kind, ClientErrorKind::MissingTarget);
+            assert_eq!(error.seq, Some(7));
+        }
+        sent => panic!("expected an error and an ack, got {:?}", sent),
+    }
+}
+
+#[test]
+fn raw_html_is_inserted_as_markup() {
+    let mut client = Client::new(vec![div().id("root").node()]);
+    client.receive(vec![Command::append_child_element(
+        "root",
+        Node::raw_html("<b>bold</b>"),
+    )]);
+    assert_eq!(client.body(), r#"<div id="root"><b>bold</b></div>"#);
+}
+
+#[test]
+fn events_send_the_id_of_their_handler() {
+    let mut node = button()
+        .id("b")
+        .on(EventKind::Onclick, |_: &mut (), _: &crate::Message| {
+            Vec::new()
+        })
+        .node();
+    crate::handlers::Handlers::<()>::new().bind(&mut node);
+    let mut client = Client::new(vec![node]);
+    let sent = client.eval(
+        "var sent = [];
+        sendMessage = (message) => sent.push(message);
+        dispatch(document.getElementById('b'), { type: 'click' });
+        JSON.stringify(sent)",
+    );
+    let sent: Vec<crate::Message> = serde_json::from_str(&sent).unwrap();
+    assert_eq!(sent[0].id, "b");
+    assert!(sent[0].handler.as_deref().unwrap().starts_with('h'));
+}
+
+/// Returns the Message the client collects for an event on the element with the given id,
+/// event is a JavaScript object literal standing in for the DOM event
+fn collect(client: &mut Client, id: &str, event: &str) -> crate::Message {
+    let json = client.eval(&format!(
+        "var event = {};
+        event.target = document.getElementById('{}');
+        JSON.stringify(eventMessage(event, event.target))",
+        event, id
+    ));
+    serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", err, json))
+}
+
+#[test]
+fn keyboard_events_send_keys_and_data_attributes() {
+    let mut client = Client::new(vec![input()
+        .id("name")
+        .r#type("text")
+        .data("data-row", "3")
+        .node()]);
+    client.eval("document.getElementById('name').value = 'Ann';");
+    let message = collect(
+        &mut client,
+        "name",
+        "{ type: 'keydown', key: 'Enter', code: 'Enter', repeat: false,
+           altKey: false, ctrlKey: true, metaKey: false, shiftKey: false }",
+    );
+
+    assert_eq!(message.event_type, "keydown");
+    assert_eq!(message.tag, "INPUT");
+    assert_eq!(message.value.as_deref(), Some("Ann"));
+    assert_eq!(message.data.get("row").map(String::as_str), Some("3"));
+    match message.event {
+        crate::Event::Keyboard(keyboard) => {
+            assert_eq!(keyboard.key, "Enter");
+            assert!(keyboard.modifiers.ctrl);
+        }
+        event => panic!("expected a keyboard event, got {:?}", event),
+    }
+}
+
+#[test]
+fn mouse_and_wheel_events_send_coordinates() {
+    let mut client = Client::new(list());
+    let mouse = "clientX: 10, clientY: 20, offsetX: 1, offsetY: 2, button: 0, buttons: 1,
+                 altKey: false, ctrlKey: false, metaKey: false, shiftKey: true";
+    let message = collect(&mut client, "a", &format!("{{ type: 'click', {} }}", mouse));
+    match message.event {
+        crate::Event::Mouse(mouse) => {
+            assert_eq!((mouse.client_x, mouse.client_y), (10.0, 20.0));
+            assert!(mouse.modifiers.shift);
+        }
+        event => panic!("expected a mouse event, got {:?}", event),
+    }
+
+    let message = collect(
+        &mut client,
+        "a",
+        &format!(
+            "{{ type: 'wheel', deltaX: 0, deltaY: 120, deltaZ: 0, deltaMode: 0, {} }}",
+            mouse
+        ),
+    );
+    match message.event {
+        crate::Event::Wheel(wheel) => assert_eq!(wheel.delta_y, 120.0),
+        event => panic!("expected a wheel event, got {:?}", event),
+    }
+}
+
+#[test]
+fn other_events_send_checked_state() {
+    let mut client = Client::new(vec![input().id("check").r#type("checkbox").node()]);
+    client.eval("document.getElementById('check').checked = true;");
+    let message = collect(&mut client, "check", "{ type: 'change' }");
+    assert_eq!(message.checked, Some(true));
+    assert_eq!(message.target_type.as_deref(), Some("checkbox"));
+    assert!(matches!(message.event, crate::Event::Other));
+}
+
+#[test]
+fn submit_sends_every_named_field_instead_of_navigating() {
+    let mut client = Client::new(vec![form()
+        .id("f")
+        .event(EventKind::Onsubmit)
+        .el(input().name("title").r#type("text"))
+        .el(input().name("count").r#type("number"))
+        .el(input().name("done").r#type("checkbox"))
+        .el(input().name("size").r#type("radio"))
+        .el(input().name("size").r#type("radio"))
+        .el(input().name("color").r#type("radio"))
+        .el(input().name("tag").r#type("checkbox"))
+        .el(input().name("tag").r#type("checkbox"))
+        .el(select()
+            .name("days")
+            .multiple()
+            .el(option())
+            .el(option())
+            .el(option()))
+        .el(textarea().name("notes"))
+        .el(input().name("hidden").r#type("text").disabled())
+        .el(input().r#type("text"))
+        .el(button().name("go"))
+        .node()]);
+    let sent = client.eval(
+        "var form = document.getElementById('f');
+        var [title, count, done, small, large, color, red, blue, days, notes, hidden, unnamed] =
+            form.elements;
+        title.value = 'Buy milk';
+        count.value = '2';
+        done.checked = true;
+        small.value = 'S'; large.value = 'L'; large.checked = true;
+        color.value = 'red';
+        red.value = 'red'; red.checked = true; blue.value = 'blue';
+        var options = days.childNodes;
+        options[0].value = 'mon'; options[1].value = 'tue'; options[2].value = 'wed';
+        options[0].selected = true; options[2].selected = true;
+        notes.value = 'Soon';
+        hidden.value = 'secret';
+        unnamed.value = 'ignored';
+
+        var sent = [];
+        sendMessage = (message) => sent.push(message);
+        var calls = dispatch(form, { type: 'submit' });
+        JSON.stringify({ calls: calls, message: sent[0] })",
+    );
+    let sent: serde_json::Value = serde_json::from_str(&sent).unwrap();
+    assert_eq!(sent["calls"], serde_json::json!(["preventDefault"]));
+
+    let message: crate::Message = serde_json::from_value(sent["message"].clone()).unwrap();
+    let text = |s: &str| FieldValue::Text(s.to_string());
+    let many = |values: &[&str]| FieldValue::Many(values.iter().map(|v| v.to_string()).collect());
+    let expected: HashMap<String, FieldValue> = vec![
+        ("title", text("Buy milk")),
+        ("count", text("2")),
+        ("done", FieldValue::Checked(true)),
+        ("size", text("L")),
+        ("color", FieldValue::Unset),
+        ("tag", many(&["red"])),
+        ("days", many(&["mon", "wed"])),
+        ("notes", text("Soon")),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+    assert_eq!(message.event_type, "submit");
+    assert_eq!(message.fields, expected);
+}
+
+#[test]
+fn events_on_other_elements_send_no_fields() {
+    let mut client = Client::new(vec![input().id("name").name("name").node()]);
+    let message = collect(&mut client, "name", "{ type: 'input' }");
+    assert!(message.fields.is_empty());
+}
+
+#[test]
+fn events_are_declared_in_an_attribute_next_to_user_event_attributes() {
+    let node = a()
+        .onclick("track()")
+        .event(EventKind::Onclick)
+        .event_with(
+            EventKind::Onmouseover,
+            EventOptions::new().prevent_default().debounce_ms(100),
+        )
+        .node();
+    assert_eq!(
+        node.into_html(),
+        concat!(
+            r#"<a onclick="track()" data-lowui-events="["#,
+            r#"{&quot;type&quot;:&quot;click&quot;},"#,
+            r#"{&quot;type&quot;:&quot;mouseover&quot;,&quot;prevent_default&quot;:true,&quot;debounce_ms&quot;:100}"#,
+            r#"]"></a>"#
+        )
+    );
+}
+
+/// Returns the ids of the elements the messages sent for an event on the target were for
+fn sent_for(client: &mut Client, target: &str, event: &str) -> String {
+    client.eval(&format!(
+        "var sent = [];
+        sendMessage = (message) => sent.push(message.handler || 'update');
+        dispatch(document.getElementById('{}'), {});
+        sent.join(' ')",
+        target, event
+    ))
+}
+
+#[test]
+fn bubbling_events_are_sent_for_ancestors_until_stopped() {
+    let mut client = Client::new(vec![div()
+        .id("outer")
+        .event(EventKind::Onclick)
+        .el(div()
+            .id("middle")
+            .event_with(EventKind::Onclick, EventOptions::new().stop_propagation())
+            .el(span().id("inner").event(EventKind::Onclick)))
+        .el(button().id("other"))
+        .node()]);
+    assert_eq!(
+        sent_for(&mut client, "inner", "{ type: 'click' }"),
+        "update update"
+    );
+    assert_eq!(
+        sent_for(&mut client, "other", "{ type: 'click' }"),
+        "update"
+    );
+}
+
+#[test]
+fn stopped_events_do_not_reach_the_listeners_of_ancestors() {
+    let mut client = Client::new(vec![div()
+        .id("outer")
+        .el(button()
+            .id("stop")
+            .event_with(EventKind::Onclick, EventOptions::new().stop_propagation()))
+        .node()]);
+    client.eval(
+        "document.getElementById('outer').addEventListener('click', () => sent.push('outer'));",
+    );
+    assert_eq!(sent_for(&mut client, "stop", "{ type: 'click' }"), "update");
+    assert_eq!(sent_for(&mut client, "outer", "{ type: 'click' }"), "outer");
+
+    client.receive(vec![Command::set_attribute(
+        "stop",
+        "data-lowui-events",
+        r#"[{"type":"click"}]"#,
+    )]);
+    assert_eq!(
+        sent_for(&mut client, "stop", "{ type: 'click' }"),
+        "outer update"
+    );
+}
+
+#[test]
+fn events_that_do_not_bubble_are_only_sent_for_their_target() {
+    let mut client = Client::new(vec![div()
+        .event(EventKind::Onmouseenter)
+        .el(span().id("inner").event(EventKind::Onmouseenter))
+        .node()]);
+    let event = "{ type: 'mouseenter', bubbles: false }";
+    assert_eq!(sent_for(&mut client, "inner", event), "update");
+}
+
+#[test]
+fn bubbled_events_are_sent_for_the_element_that_declared_them() {
+    let mut client = Client::new(vec![button()
+        .id("delete")
+        .data("data-row", "3")
+        .event(EventKind::Onclick)
+        .el(span().id("icon").data("data-icon", "trash"))
+        .node()]);
+    let json = client.eval(
+        "var sent = [];
+        sendMessage = (message) => sent.push(message);
+        dispatch(document.getElementById('icon'), { type: 'click' });
+        JSON.stringify(sent[0])",
+    );
+    let message: crate::Message =
+        serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", err, json));
+
+    assert_eq!(message.id, "delete");
+    assert_eq!(message.tag, "BUTTON");
+    assert_eq!(message.data.len(), 1);
+    assert_eq!(message.data.get("row").map(String::as_str), Some("3"));
+}
+
+#[test]
+fn events_declared_by_commands_are_listened_for() {
+    let mut client = Client::new(vec![button().id("b").node()]);
+    assert_eq!(sent_for(&mut client, "b", "{ type: 'dblclick' }"), "");
+    let events = r#"[{"type":"dblclick"}]"#;
+    client.receive(vec![Command::set_attribute(
+        "b",
+        "data-lowui-events",
+        events,
+    )]);
+    assert_eq!(sent_for(&mut client, "b", "{ type: 'dblclick' }"), "update");
+}
+
+/// Adds an input with the given id and options for its input event, then runs the script,
+/// which calls input(value) for each event and may advance the clock with tick,
+/// returns the values of the messages sent and the calls made on the events
+fn inputs(client: &mut Client, id: &str, options: EventOptions, script: &str) -> String {
+    let node = input()
+        .id(id)
+        .event_with(EventKind::Oninput, options)
+        .node();
+    client.eval(&format!(
+        "document.body.appendChild(makeElement({}));",
+        serde_json::to_string(&node).unwrap()
+    ));
+    client.eval(&format!(
+        "var sent = [];
+        sendMessage = (message) => sent.push(message.value);
+        var element = document.getElementById('{}');
+        function input(value) {{
+            element.value = value;
+            dispatch(element, {{ type: 'input' }}).forEach((call) => sent.push(call));
+        }}
+        {}
+        sent.join(' ')",
+        id, script
+    ))
+}
+
+#[test]
+fn prevent_default_and_stop_propagation_are_called() {
+    let mut client = Client::new(Vec::new());
+    let options = EventOptions::new().prevent_default().stop_propagation();
+    let sent = inputs(&mut client, "i", options, "input('a');");
+    assert_eq!(sent, "a preventDefault stopPropagation");
+}
+
+#[test]
+fn debounced_events_send_the_last_once_they_stop() {
+    let mut client = Client::new(Vec::new());
+    let options = EventOptions::new().debounce_ms(100);
+    let sent = inputs(
+        &mut client,
+        "i",
+        options,
+        "input('a'); tick(50); input('ab'); tick(99); sent.push('|');
+        tick(1); input('abc'); tick(100);",
+    );
+    assert_eq!(sent, "| ab abc");
+}
+
+#[test]
+fn throttled_events_send_at_most_one_per_interval() {
+    let mut client = Client::new(Vec::new());
+    let options = EventOptions::new().throttle_ms(100);
+    let sent = inputs(
+        &mut client,
+        "i",
+        options,
+        "input('a'); tick(10); input('ab'); tick(10); input('abc'); sent.push('|');
+        tick(80); sent.push('|'); tick(100); input('abcd');",
+    );
+    assert_eq!(sent, "a | abc | abcd");
+}
+
+#[test]
+fn once_sends_only_the_first_event() {
+    let mut client = Client::new(Vec::new());
+    let sent = inputs(
+        &mut client,
+        "i",
+        EventOptions::new().once(),
+        "input('a'); input('b');",
+    );
+    assert_eq!(sent, "a");
+}
+
+#[test]
+fn style_text_is_not_escaped() {
+    let node = style()
+        .text("ul > li { color: red } a[title=\"&\"] { content: '</STYLE>' }")
+        .node();
+    assert_eq!(
+        node.into_html(),
+        r#"<style>ul > li { color: red } a[title="&"] { content: '<\/STYLE>' }</style>"#
+    );
+}
+
+#[test]
+fn text_is_escaped() {
+    let node = p().text("<script>alert('&')</script>").node();
+    assert_eq!(
+        node.into_html(),
+        "<p>&lt;script&gt;alert('&amp;')&lt;/script&gt;</p>"
+    );
+}
+
+#[test]
+fn attribute_values_are_escaped() {
+    let node = div().title(r#"say "hi" & 'bye' <now>"#).node();
+    assert_eq!(
+        node.into_html(),
+        r#"<div title="say &quot;hi&quot; &amp; &#39;bye&#39; &lt;now&gt;"></div>"#
+    );
+}
+
+#[test]
+fn name_only_attributes_have_no_value() {
+    let node = input().r#type("checkbox").checked().node();
+    assert_eq!(node.into_html(), r#"<input type="checkbox" checked>"#);
+}
+
+#[test]
+fn raw_html_is_not_escaped() {
+    let node = div().el(Node::raw_html("<b>&amp;</b>")).node();
+    assert_eq!(node.into_html(), "<div><b>&amp;</b></div>");
+}
+
+#[test]
+fn page_escapes_title_meta_and_nodes() {
+    let html = HtmlPage::new("Tom & Jerry <3")
+        .meta_content("description", r#"a "quoted" description"#)
+        .link(Link::new("stylesheet").href("css/index.css?a=1&b=2"))
+        .node(p().id("greeting").text("1 < 2"))
+        .into_html("/ws?session=abc&x=1");
+
+    assert!(html.contains("<title>Tom &amp; Jerry &lt;3</title>"));
+    assert!(
+        html.contains(r#"<meta name="description" content="a &quot;quoted&quot; description">"#)
+    );
+    assert!(html.contains(r#"<link rel="stylesheet" href="css/index.css?a=1&amp;b=2">"#));
+    assert!(html.contains(r#"<p id="greeting">1 &lt; 2</p>"#));
+    assert!(html.contains(
+        r#"<script src="/lowui/client.js" data-ws-path="/ws?session=abc&amp;x=1"></script>"#
+    ));
+}
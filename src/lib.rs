@@ -11,43 +11,92 @@
 //! state, client/server logic separation and the need to write
 //! JavaScript.
 
-#![feature(proc_macro_hygiene, decl_macro)]
-#[macro_use]
-extern crate rocket;
-
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+// Lets the code html! expands to refer to ::lowui inside this crate too
+extern crate self as lowui;
 
 pub mod builders;
+mod connection;
+mod error;
+mod handlers;
 pub mod html;
+mod message;
 mod server;
+mod vdom;
 
-pub use self::server::start;
+pub use self::connection::{ClientHandle, ClientInfo, Clients, ConnectionId, RequestInfo};
+pub use self::error::{Error, Result};
+pub use self::message::{
+    ClientError, ClientErrorKind, Event, FieldValue, KeyboardEvent, Message, Modifiers, MouseEvent,
+    WheelEvent,
+};
+pub use self::server::{
+    start, start_with_config, start_with_config_and_state, start_with_state, ServerConfig,
+};
+pub use lowui_macros::html;
 
-/// Trait representing the user application
-pub trait App {
+/// Trait representing the user application,
+/// S is the type of the state shared by every client, see `start_with_state`
+pub trait App<S = ()> {
     /// Returns an instance, this will be called once for every client connection
-    fn new() -> Self;
+    fn new(shared: Arc<S>) -> Self;
+
+    /// Returns an HTML page, by default this is the page `render` returns for every request
+    fn init() -> html::HtmlPage {
+        html::HtmlPage::new("lowui")
+    }
+
+    /// Returns the HTML page for a request, this is called on a new instance for every
+    /// page load and that same instance then handles the page's WebSocket connection
+    fn render(&self, _request: &RequestInfo) -> html::HtmlPage {
+        Self::init()
+    }
 
-    /// Returns an HTML page, this will be called once before the server starts
-    fn init() -> html::HtmlPage;
+    /// Returns the part of the UI that is kept in sync with the application state,
+    /// it is added to the rendered page and after every `update` and `on_connect`
+    /// the client is sent the commands that turn the previous view into the new one.
+    /// Elements without an id are given one. By default there is no view
+    fn view(&self) -> Option<html::Node> {
+        None
+    }
 
     /// Updates the application state and returns commands to update the UI
     /// this is called on every client event
     fn update(&mut self, message: Message) -> Vec<Command>;
-}
 
-#[derive(Debug, Deserialize)]
-pub struct Message {
-    pub id: String,
-    pub r#type: String,
-    pub name: Option<String>,
-    pub value: Option<String>,
-    pub checked: Option<bool>,
+    /// Called once the client's WebSocket connection is open,
+    /// the returned commands are sent to the client immediately
+    fn on_connect(&mut self, _info: &ClientInfo) -> Vec<Command> {
+        Vec::new()
+    }
+
+    /// Called when the client disconnects, the instance is dropped afterwards
+    fn on_disconnect(&mut self) {}
+
+    /// Called when an error occurs on this client's connection,
+    /// by default the error is printed to stderr
+    fn on_error(&mut self, error: &Error) {
+        eprintln!("lowui: {}", error);
+    }
+
+    /// Called when the client fails to apply a command, such as one whose target
+    /// does not exist, by default the error is printed to stderr
+    fn on_client_error(&mut self, error: ClientError) {
+        eprintln!("lowui: {}", error);
+    }
+
+    /// Called when the client has applied the batch of commands with the given sequence number.
+    /// Batches are only numbered if `ServerConfig::acknowledge` is set, they are numbered
+    /// from 1 in the order they are sent on the connection, whether they come from
+    /// `on_connect`, an event or a `ClientHandle`
+    fn on_ack(&mut self, _seq: u64) {}
 }
 
 /// The type of command to execute in the client
 /// These represent manipulations of the DOM
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub enum CommandType {
     None,
     AppendChildElement(html::Node),
@@ -56,15 +105,89 @@ pub enum CommandType {
     RemoveElement,
     SetAttribute(html::Attr),
     RemoveAttribute(&'static str),
+    /// Sets a property of the DOM element, unlike attributes properties such as value
+    /// and checked reflect what the user entered
+    SetProperty {
+        name: String,
+        value: serde_json::Value,
+    },
+    MoveElementBefore(String),
+    MoveElementToEnd,
+    AddClass(String),
+    RemoveClass(String),
+    /// Adds the class if the element does not have it and removes it if it does,
+    /// or adds it if force is true and removes it if force is false
+    ToggleClass {
+        name: String,
+        force: Option<bool>,
+    },
+    /// Sets a CSS property in the element's inline style
+    SetStyle {
+        property: String,
+        value: String,
+    },
+    /// Removes a CSS property from the element's inline style
+    RemoveStyle(String),
+    /// Replaces the element's children with the text
+    SetText(String),
+    /// Sets the text of the element's child node at the index, which must be a text node
+    SetChildText {
+        index: usize,
+        text: String,
+    },
+    ReplaceChildren(Vec<html::Node>),
+    ClearChildren,
+}
+
+/// The elements a command is executed on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Target {
+    /// The element with the id
+    Id(String),
+    /// The first element matching the CSS selector
+    Selector(String),
+    /// Every element matching the CSS selector, the command is executed on each
+    SelectorAll(String),
+}
+
+impl Target {
+    /// Returns a target for the first element matching the CSS selector
+    pub fn selector<T: Into<String>>(selector: T) -> Self {
+        Target::Selector(selector.into())
+    }
+
+    /// Returns a target for every element matching the CSS selector
+    pub fn selector_all<T: Into<String>>(selector: T) -> Self {
+        Target::SelectorAll(selector.into())
+    }
+}
+
+/// Strings target the element with that id
+impl From<&str> for Target {
+    fn from(id: &str) -> Self {
+        Target::Id(id.to_string())
+    }
+}
+
+impl From<String> for Target {
+    fn from(id: String) -> Self {
+        Target::Id(id)
+    }
+}
+
+impl From<&String> for Target {
+    fn from(id: &String) -> Self {
+        Target::Id(id.clone())
+    }
 }
 
 /// A command to execute on the client
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Command {
     /// The type of command
     pub command_type: CommandType,
-    /// The ID of the element affected by the command
-    pub id: Option<String>,
+    /// The elements affected by the command
+    pub target: Option<Target>,
 }
 
 impl Command {
@@ -72,67 +195,224 @@ impl Command {
     pub fn none() -> Command {
         Command {
             command_type: CommandType::None,
-            id: None,
+            target: None,
         }
     }
 
-    /// Returns a command to append a child Node to an element identified by id
-    pub fn append_child_element<T: Into<String>>(id: T, node: html::Node) -> Command {
+    /// Returns a command to append a child Node to the element given by target
+    pub fn append_child_element<T: Into<Target>>(target: T, node: html::Node) -> Command {
         Command {
             command_type: CommandType::AppendChildElement(node),
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to insert a child Node before an element identified by id
-    pub fn insert_element_before<T: Into<String>>(id: T, node: html::Node) -> Command {
+    /// Returns a command to insert a child Node before the element given by target
+    pub fn insert_element_before<T: Into<Target>>(target: T, node: html::Node) -> Command {
         Command {
             command_type: CommandType::InsertElementBefore(node),
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to update an element given by id by replacing it with a new Node
-    pub fn replace_element<T: Into<String>>(id: T, node: html::Node) -> Command {
+    /// Returns a command to update the element given by target by replacing it with a new Node
+    pub fn replace_element<T: Into<Target>>(target: T, node: html::Node) -> Command {
         Command {
             command_type: CommandType::ReplaceElement(node),
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to delete an element given by id
-    pub fn remove_element<T: Into<String>>(id: T) -> Command {
+    /// Returns a command to delete the element given by target
+    pub fn remove_element<T: Into<Target>>(target: T) -> Command {
         Command {
             command_type: CommandType::RemoveElement,
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to set (add or update) an attribute on the element given by id
-    pub fn set_attribute<T: Into<String>, U: Into<String>>(
-        id: T,
+    /// Returns a command to move the element given by target before the element given by before_id,
+    /// the element keeps its state, such as focus and input values
+    pub fn move_element_before<T: Into<Target>, U: Into<String>>(
+        target: T,
+        before_id: U,
+    ) -> Command {
+        Command {
+            command_type: CommandType::MoveElementBefore(before_id.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to move the element given by target to the end of its parent
+    pub fn move_element_to_end<T: Into<Target>>(target: T) -> Command {
+        Command {
+            command_type: CommandType::MoveElementToEnd,
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to set (add or update) an attribute on the element given by target
+    pub fn set_attribute<T: Into<Target>, U: Into<String>>(
+        target: T,
         name: &'static str,
         value: U,
     ) -> Command {
         Command {
             command_type: CommandType::SetAttribute(html::Attr::new(name, value)),
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to set (add or update) a name-only attribute on the element given by id
-    pub fn set_name_only_attribute<T: Into<String>>(id: T, name: &'static str) -> Command {
+    /// Returns a command to set (add or update) a name-only attribute on the element given by target
+    pub fn set_name_only_attribute<T: Into<Target>>(target: T, name: &'static str) -> Command {
         Command {
             command_type: CommandType::SetAttribute(html::Attr::name_only(name)),
-            id: Some(id.into()),
+            target: Some(target.into()),
         }
     }
 
-    /// Returns a command to remove an attribute from the element given by id
-    pub fn remove_attribute<T: Into<String>>(id: T, name: &'static str) -> Command {
+    /// Returns a command to remove an attribute from the element given by target
+    pub fn remove_attribute<T: Into<Target>>(target: T, name: &'static str) -> Command {
         Command {
             command_type: CommandType::RemoveAttribute(name),
-            id: Some(id.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to set a property of the DOM element given by target,
+    /// the value is assigned as the equivalent JavaScript value
+    pub fn set_property<T: Into<Target>, U: Into<String>, V: Into<serde_json::Value>>(
+        target: T,
+        name: U,
+        value: V,
+    ) -> Command {
+        Command {
+            command_type: CommandType::SetProperty {
+                name: name.into(),
+                value: value.into(),
+            },
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to set the current value of an input, textarea or select,
+    /// such as clearing a text box after it was submitted
+    pub fn set_value<T: Into<Target>, U: Into<String>>(target: T, value: U) -> Command {
+        Self::set_property(target, "value", value.into())
+    }
+
+    /// Returns a command to check or uncheck a checkbox or radio button
+    pub fn set_checked<T: Into<Target>>(target: T, checked: bool) -> Command {
+        Self::set_property(target, "checked", checked)
+    }
+
+    /// Returns a command to disable or enable a control
+    pub fn set_disabled<T: Into<Target>>(target: T, disabled: bool) -> Command {
+        Self::set_property(target, "disabled", disabled)
+    }
+
+    /// Returns a command to select the option of a select at the given index,
+    /// -1 selects no option
+    pub fn set_selected_index<T: Into<Target>>(target: T, index: i32) -> Command {
+        Self::set_property(target, "selectedIndex", index)
+    }
+
+    /// Returns a command to add a class to the element given by target
+    pub fn add_class<T: Into<Target>, U: Into<String>>(target: T, name: U) -> Command {
+        Command {
+            command_type: CommandType::AddClass(name.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to remove a class from the element given by target
+    pub fn remove_class<T: Into<Target>, U: Into<String>>(target: T, name: U) -> Command {
+        Command {
+            command_type: CommandType::RemoveClass(name.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to toggle a class of the element given by target,
+    /// with Some(force) the class is added if force is true and removed otherwise
+    pub fn toggle_class<T: Into<Target>, U: Into<String>>(
+        target: T,
+        name: U,
+        force: Option<bool>,
+    ) -> Command {
+        Command {
+            command_type: CommandType::ToggleClass {
+                name: name.into(),
+                force,
+            },
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to set a CSS property, such as color, in the inline style
+    /// of the element given by target
+    pub fn set_style<T: Into<Target>, U: Into<String>, V: Into<String>>(
+        target: T,
+        property: U,
+        value: V,
+    ) -> Command {
+        Command {
+            command_type: CommandType::SetStyle {
+                property: property.into(),
+                value: value.into(),
+            },
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to remove a CSS property from the inline style
+    /// of the element given by target
+    pub fn remove_style<T: Into<Target>, U: Into<String>>(target: T, property: U) -> Command {
+        Command {
+            command_type: CommandType::RemoveStyle(property.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to replace the children of the element given by target with text
+    pub fn set_text<T: Into<Target>, U: Into<String>>(target: T, text: U) -> Command {
+        Command {
+            command_type: CommandType::SetText(text.into()),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to set the text of the child node at index of the element given
+    /// by target, the child must be a text node. Adjacent text is a single node in the client
+    /// and markup can be any number of nodes, so count nodes as the client has them
+    pub fn set_child_text<T: Into<Target>, U: Into<String>>(
+        target: T,
+        index: usize,
+        text: U,
+    ) -> Command {
+        Command {
+            command_type: CommandType::SetChildText {
+                index,
+                text: text.into(),
+            },
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to replace the children of the element given by target with nodes,
+    /// use `html::Node::raw_html` to set the element's inner HTML
+    pub fn replace_children<T: Into<Target>>(target: T, nodes: Vec<html::Node>) -> Command {
+        Command {
+            command_type: CommandType::ReplaceChildren(nodes),
+            target: Some(target.into()),
+        }
+    }
+
+    /// Returns a command to remove every child of the element given by target
+    pub fn clear_children<T: Into<Target>>(target: T) -> Command {
+        Command {
+            command_type: CommandType::ClearChildren,
+            target: Some(target.into()),
         }
     }
 }
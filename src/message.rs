@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::Target;
+
+/// What a client sends over its WebSocket connection
+#[derive(Debug, Deserialize)]
+pub(crate) enum Incoming {
+    Event(Box<Message>),
+    Error(ClientError),
+    /// The client applied the batch of commands with this sequence number
+    Ack(u64),
+}
+
+/// An event in the client, passed to `App::update` or to the handler bound with `on`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    /// The id of the element the event was declared on, empty if it has none
+    pub id: String,
+    /// The DOM event type, such as click, input or keydown
+    pub event_type: String,
+    /// The tag name of the element the event was declared on in upper case, such as BUTTON
+    pub tag: String,
+    /// The target's value, for elements that have one such as inputs and selects
+    pub value: Option<String>,
+    /// Whether the target is checked, for checkboxes and radio buttons
+    pub checked: Option<bool>,
+    /// The target's type, such as checkbox for inputs or submit for buttons
+    pub target_type: Option<String>,
+    /// The data-* attributes of the element the event was declared on,
+    /// keyed by name without the data- prefix
+    #[serde(default)]
+    pub data: HashMap<String, String>,
+    /// Details that depend on the kind of event
+    #[serde(default)]
+    pub event: Event,
+    /// The value of every named control, for events on forms such as submit
+    #[serde(default)]
+    pub fields: HashMap<String, FieldValue>,
+    /// The id of the handler bound with `on` the event is for, None for events
+    /// passed to `App::update`
+    pub handler: Option<String>,
+}
+
+/// The details of an event
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "kind")]
+pub enum Event {
+    Mouse(MouseEvent),
+    Keyboard(KeyboardEvent),
+    Wheel(WheelEvent),
+    /// Events without details, such as change and input
+    #[default]
+    Other,
+}
+
+/// The value of a named form control
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum FieldValue {
+    /// The value of a text-like input such as text or number, a textarea, a select,
+    /// or the checked button of a radio group
+    Text(String),
+    /// Whether a checkbox is checked
+    Checked(bool),
+    /// The selected options of a multiple select,
+    /// or the values of the checked boxes when several checkboxes share the name
+    Many(Vec<String>),
+    /// A radio group without a checked button
+    Unset,
+}
+
+impl FieldValue {
+    /// Returns the value if it is Text
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// The keys held down during an event
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Modifiers {
+    pub alt: bool,
+    pub ctrl: bool,
+    pub meta: bool,
+    pub shift: bool,
+}
+
+/// Details of mouse events such as click, mousedown and mousemove
+#[derive(Debug, Clone, Deserialize)]
+pub struct MouseEvent {
+    /// Horizontal position in the viewport
+    pub client_x: f64,
+    /// Vertical position in the viewport
+    pub client_y: f64,
+    /// Horizontal position relative to the target's padding edge
+    pub offset_x: f64,
+    /// Vertical position relative to the target's padding edge
+    pub offset_y: f64,
+    /// The button that changed, 0 is the main button
+    pub button: i16,
+    /// The buttons held down, one bit for each button
+    pub buttons: u16,
+    pub modifiers: Modifiers,
+}
+
+/// Details of keyboard events such as keydown and keyup
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyboardEvent {
+    /// The value of the key, such as a, A or Enter
+    pub key: String,
+    /// The physical key, such as KeyA or Enter
+    pub code: String,
+    /// True if the key is held down and repeating
+    pub repeat: bool,
+    pub modifiers: Modifiers,
+}
+
+/// Details of wheel events
+#[derive(Debug, Clone, Deserialize)]
+pub struct WheelEvent {
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub delta_z: f64,
+    /// The unit of the deltas, 0 for pixels, 1 for lines and 2 for pages
+    pub delta_mode: u32,
+    pub mouse: MouseEvent,
+}
+
+/// A command the client could not apply, passed to `App::on_client_error`.
+/// The client goes on with the rest of the batch
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientError {
+    pub kind: ClientErrorKind,
+    /// The command's type, such as SetAttribute
+    pub command_type: String,
+    /// The command's target
+    pub target: Option<Target>,
+    /// What went wrong, such as the message of the exception thrown
+    pub message: String,
+    /// The sequence number of the command's batch, if batches are numbered,
+    /// see `ServerConfig::acknowledge`
+    pub seq: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ClientErrorKind {
+    /// No element matched the command's target
+    MissingTarget,
+    /// The client does not know the command's type
+    UnknownCommand,
+    /// Applying the command threw an exception
+    Exception,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Client could not apply {}: {}",
+            self.command_type, self.message
+        )
+    }
+}
@@ -1,75 +1,665 @@
 use std::fs;
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{sleep, spawn};
+use std::time::{Duration, Instant};
 
-use rocket::response::content::{ Html, Css };
-use rocket::State;
+use serde::Serialize;
+use tungstenite::http::{header, HeaderValue, Request, Response, StatusCode};
+use tungstenite::WebSocket;
 
-use std::net::TcpListener;
-use std::thread;
-use std::thread::spawn;
+use crate::connection::{
+    next_connection_id, query_param, ClientHandle, ClientInfo, Clients, RequestInfo, Wakeup,
+};
+use crate::error::{Error, Result};
+use crate::handlers::Handlers;
+use crate::message::Incoming;
+use crate::vdom::Vdom;
 
-use tungstenite::accept;
+mod http;
+mod sessions;
+mod socket;
+#[cfg(test)]
+mod tests;
 
-#[get("/")]
-fn index(html: State<String>) -> Html<String> {
-    Html(html.inner().to_string())
+use self::sessions::Sessions;
+use self::socket::Socket;
+
+/// The query parameter a page passes its session token to the WebSocket endpoint in
+const SESSION_PARAM: &str = "session";
+
+/// How long a connection may take to send its request before it is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration for the server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// The address the server binds to
+    pub address: String,
+    /// The port the server listens on, for both HTTP and WebSocket connections
+    pub port: u16,
+    /// The path requests are upgraded to WebSocket connections on
+    pub ws_path: String,
+    /// The directory pages requested under /html/ are served from
+    pub html_dir: PathBuf,
+    /// The directory stylesheets requested under /css/ are served from
+    pub css_dir: PathBuf,
+    /// If true every batch of commands is sent with a sequence number
+    /// and the client acknowledges it once applied, see `App::on_ack`
+    pub acknowledge: bool,
+    /// How long a connection may be silent before the client is pinged,
+    /// a client that sends nothing back for as long again is disconnected
+    pub ping_interval: Duration,
+}
+
+impl ServerConfig {
+    /// Returns a config listening on localhost port 8000 with WebSockets on /ws,
+    /// serving static files from the "static" and "css" directories next to the executable
+    pub fn new() -> Self {
+        let exe_dir = exe_dir();
+        Self {
+            address: "localhost".to_string(),
+            port: 8000,
+            ws_path: "/ws".to_string(),
+            html_dir: exe_dir.join("static"),
+            css_dir: exe_dir.join("css"),
+            acknowledge: false,
+            ping_interval: Duration::from_secs(30),
+        }
+    }
+
+    pub fn address<T: Into<String>>(mut self, address: T) -> Self {
+        self.address = address.into();
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn ws_path<T: Into<String>>(mut self, path: T) -> Self {
+        self.ws_path = path.into();
+        self
+    }
+
+    pub fn html_dir<T: Into<PathBuf>>(mut self, dir: T) -> Self {
+        self.html_dir = dir.into();
+        self
+    }
+
+    pub fn css_dir<T: Into<PathBuf>>(mut self, dir: T) -> Self {
+        self.css_dir = dir.into();
+        self
+    }
+
+    pub fn acknowledge(mut self, acknowledge: bool) -> Self {
+        self.acknowledge = acknowledge;
+        self
+    }
+
+    pub fn ping_interval(mut self, interval: Duration) -> Self {
+        self.ping_interval = interval;
+        self
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn exe_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// State shared by every connection
+struct Context<T, S> {
+    config: ServerConfig,
+    clients: Clients,
+    shared: Arc<S>,
+    sessions: Sessions<Rendered<T>>,
+}
+
+impl<T, S> Context<T, S> {
+    fn new(config: ServerConfig, state: S) -> Self {
+        Self {
+            config,
+            clients: Clients::new(),
+            shared: Arc::new(state),
+            sessions: Sessions::new(),
+        }
+    }
 }
 
-#[get("/html/<page>")]
-fn html(page: String) -> Html<String> {
-    let mut path = std::path::PathBuf::from(std::env::current_exe().unwrap().parent().unwrap());
-    path.push("static");
-    path.push(page);
-    Html(fs::read_to_string(path)
-        .expect("Something went wrong reading the file"))
+/// An app, the last view it rendered if it has a view,
+/// and the handlers bound in everything it sent to its client
+struct Rendered<T> {
+    app: T,
+    view: Option<Vdom>,
+    handlers: Handlers<T>,
 }
 
-#[get("/css/<page>")]
-fn css(page: String) -> Css<String> {
-    let mut path = std::path::PathBuf::from(std::env::current_exe().unwrap().parent().unwrap());
-    path.push("css");
-    path.push(page);
-    Css(fs::read_to_string(path)
-        .expect("Something went wrong reading the file"))
+impl<T: 'static> Rendered<T> {
+    /// Renders the app's view for the first time, returns the view to put on the page
+    fn new<S>(app: T) -> (Self, Option<crate::html::Node>)
+    where
+        T: crate::App<S>,
+    {
+        let mut rendered = Self {
+            view: None,
+            handlers: Handlers::new(),
+            app,
+        };
+        let node = rendered.app.view().map(|node| {
+            let (mut view, node) = Vdom::mount(node);
+            rendered.handlers.bind_view(view.view_mut());
+            rendered.view = Some(view);
+            node
+        });
+        rendered.report_handler_errors();
+        (rendered, node)
+    }
+
+    /// Passes closures bound since the last call that the app can not be called with
+    /// to `on_error`
+    fn report_handler_errors<S>(&mut self)
+    where
+        T: crate::App<S>,
+    {
+        for err in self.handlers.take_errors() {
+            self.app.on_error(&err);
+        }
+    }
+
+    /// Passes the message to the handler it is for, or to `update` if there is none
+    fn handle<S>(&mut self, message: crate::Message) -> Vec<crate::Command>
+    where
+        T: crate::App<S>,
+    {
+        let handler = message
+            .handler
+            .as_deref()
+            .and_then(|id| self.handlers.get(id));
+        let commands = match handler {
+            Some(handler) => handler(&mut self.app, &message),
+            None => self.app.update(message),
+        };
+        self.outgoing(commands)
+    }
+
+    /// Binds the handlers in commands from the app and adds the changes to its view,
+    /// returns the commands to send
+    fn outgoing<S>(&mut self, mut commands: Vec<crate::Command>) -> Vec<crate::Command>
+    where
+        T: crate::App<S>,
+    {
+        self.handlers.bind_commands(&mut commands);
+        if let (Some(view), Some(node)) = (self.view.as_mut(), self.app.view()) {
+            let changes = view.update(node);
+            self.handlers.forget_removed(&changes);
+            commands.extend(changes);
+            self.handlers.bind_view(view.view_mut());
+        }
+        self.report_handler_errors();
+        commands
+    }
+}
+
+fn index<T: crate::App<S> + 'static, S>(
+    request: &Request<()>,
+    peer_addr: SocketAddr,
+    context: &Context<T, S>,
+) -> Result<Response<Vec<u8>>> {
+    let app = T::new(Arc::clone(&context.shared));
+    let mut page = app.render(&request_info(request, peer_addr));
+    let (mut rendered, view) = Rendered::new(app);
+    for node in &mut page.nodes {
+        rendered.handlers.bind(node);
+    }
+    rendered.report_handler_errors();
+    page.nodes.extend(view);
+    let token = context.sessions.insert(rendered)?;
+    let ws_url = format!("{}?{}={}", context.config.ws_path, SESSION_PARAM, token);
+    Ok(content(
+        "text/html; charset=utf-8",
+        page.into_html(&ws_url).into_bytes(),
+    ))
+}
+
+fn html<T, S>(page: &str, context: &Context<T, S>) -> Result<Response<Vec<u8>>> {
+    let body = read_file(&context.config.html_dir, page)?;
+    Ok(content("text/html; charset=utf-8", body))
+}
+
+fn css<T, S>(page: &str, context: &Context<T, S>) -> Result<Response<Vec<u8>>> {
+    let body = read_file(&context.config.css_dir, page)?;
+    Ok(content("text/css; charset=utf-8", body))
 }
 
-fn http_init(html: String) {
-    rocket::ignite()
-        .manage(html)
-        .mount("/", routes![index, html, css])
-        .launch();
+fn read_file(dir: &Path, page: &str) -> Result<Vec<u8>> {
+    fs::read(dir.join(page)).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => Error::NotFound(page.to_string()),
+        _ => Error::Io(err),
+    })
 }
 
-/// Starts the server, every request from the client will spawn a new thread
-/// with a new instance of the type given as a type parameter
-pub fn start<T: crate::App>() {
-    let page = T::init();
+fn content(content_type: &'static str, body: Vec<u8>) -> Response<Vec<u8>> {
+    let mut response = Response::new(body);
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+fn status(status: StatusCode) -> Response<Vec<u8>> {
+    let reason = status.canonical_reason().unwrap_or("");
+    let mut response = content("text/plain; charset=utf-8", reason.as_bytes().to_vec());
+    *response.status_mut() = status;
+    response
+}
+
+/// Returns the file name if path is a single segment under prefix, such as /css/index.css
+fn static_file<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let page = path.strip_prefix(prefix)?;
+    if page.is_empty() || page.contains('/') || page.contains('\\') || page == ".." {
+        None
+    } else {
+        Some(page)
+    }
+}
 
-    let html = page.into_html();
+fn route<T: crate::App<S> + 'static, S>(
+    request: &Request<()>,
+    peer_addr: SocketAddr,
+    context: &Context<T, S>,
+) -> Result<Response<Vec<u8>>> {
+    let path = request.uri().path();
+    if path == "/" {
+        index(request, peer_addr, context)
+    } else if let Some(page) = static_file(path, "/html/") {
+        html(page, context)
+    } else if let Some(page) = static_file(path, "/css/") {
+        css(page, context)
+    } else if path == crate::html::CLIENT_SCRIPT_PATH {
+        Ok(content(
+            "text/javascript; charset=utf-8",
+            crate::html::CLIENT_SCRIPT.as_bytes().to_vec(),
+        ))
+    } else {
+        Err(Error::NotFound(path.to_string()))
+    }
+}
+
+/// Routes a request that is not for the WebSocket endpoint, errors are turned into responses
+fn respond<T: crate::App<S> + 'static, S>(
+    request: &Request<()>,
+    peer_addr: SocketAddr,
+    context: &Context<T, S>,
+) -> Response<Vec<u8>> {
+    match route(request, peer_addr, context) {
+        Ok(response) => response,
+        Err(Error::NotFound(_)) => status(StatusCode::NOT_FOUND),
+        Err(err) => {
+            report(&err);
+            status(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Reports errors that can not be passed to an App
+fn report(error: &Error) {
+    eprintln!("lowui: {}", error);
+}
+
+/// Numbers the batches of commands sent on a connection if the client acknowledges them
+struct Batches {
+    /// The number of the next batch, None if batches are not numbered
+    next_seq: Option<u64>,
+}
 
-    thread::spawn(move || {
-        http_init(html);
+/// A numbered batch, batches that are not numbered are sent as a plain array of commands
+#[derive(Serialize)]
+struct Batch<'a> {
+    seq: u64,
+    commands: &'a [crate::Command],
+}
+
+impl Batches {
+    fn new(acknowledge: bool) -> Self {
+        Self {
+            next_seq: if acknowledge { Some(1) } else { None },
+        }
+    }
+
+    fn send(
+        &mut self,
+        websocket: &mut WebSocket<Socket>,
+        commands: &[crate::Command],
+    ) -> Result<()> {
+        let json = match self.next_seq.as_mut() {
+            Some(seq) => {
+                let batch = Batch {
+                    seq: *seq,
+                    commands,
+                };
+                *seq += 1;
+                serde_json::to_string(&batch)
+            }
+            None => serde_json::to_string(commands),
+        }
+        .map_err(Error::Encode)?;
+        websocket.write_message(tungstenite::Message::Text(json))?;
+        Ok(())
+    }
+}
+
+/// Tracks when the client was last heard from, so that a client that went away
+/// without closing its connection is noticed
+struct Liveness {
+    interval: Duration,
+    last_heard: Instant,
+    pinged: bool,
+}
+
+/// What to do about a connection the client has not sent anything on for a while
+enum Check {
+    Alive,
+    Ping,
+    Gone,
+}
+
+impl Liveness {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_heard: Instant::now(),
+            pinged: false,
+        }
+    }
+
+    /// Records that the client sent something, a pong or any other message
+    fn heard(&mut self) {
+        self.last_heard = Instant::now();
+        self.pinged = false;
+    }
+
+    /// Returns how long until the client should be checked on
+    fn until_check(&self) -> Duration {
+        let deadline = if self.pinged {
+            self.interval * 2
+        } else {
+            self.interval
+        };
+        deadline.saturating_sub(self.last_heard.elapsed())
+    }
+
+    fn check(&mut self) -> Check {
+        let silent = self.last_heard.elapsed();
+        if self.pinged && silent >= self.interval * 2 {
+            Check::Gone
+        } else if !self.pinged && silent >= self.interval {
+            self.pinged = true;
+            Check::Ping
+        } else {
+            Check::Alive
+        }
+    }
+}
+
+/// Sends commands pushed through a ClientHandle
+fn send_pushed<T: crate::App<S> + 'static, S>(
+    rendered: &mut Rendered<T>,
+    mut commands: Vec<crate::Command>,
+    batches: &mut Batches,
+    websocket: &mut WebSocket<Socket>,
+) -> Result<()> {
+    rendered.handlers.bind_commands(&mut commands);
+    rendered.report_handler_errors();
+    batches.send(websocket, &commands)
+}
+
+fn handle_message<T: crate::App<S> + 'static, S>(
+    rendered: &mut Rendered<T>,
+    batches: &mut Batches,
+    websocket: &mut WebSocket<Socket>,
+    msg: tungstenite::Message,
+) -> Result<()> {
+    if msg.is_text() {
+        let text = msg.to_text()?;
+        match serde_json::from_str(text).map_err(Error::Decode)? {
+            Incoming::Event(message) => {
+                let commands = rendered.handle(*message);
+                batches.send(websocket, &commands)?;
+            }
+            Incoming::Error(error) => rendered.app.on_client_error(error),
+            Incoming::Ack(seq) => rendered.app.on_ack(seq),
+        }
+    }
+    Ok(())
+}
+
+fn request_info(request: &Request<()>, peer_addr: SocketAddr) -> RequestInfo {
+    RequestInfo {
+        path: request.uri().path().to_string(),
+        query: request.uri().query().map(String::from),
+        headers: request
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.as_str().to_string(), value.to_string()))
+            })
+            .collect(),
+        peer_addr,
+    }
+}
+
+fn run<T: crate::App<S> + 'static, S>(
+    mut stream: TcpStream,
+    context: &Context<T, S>,
+) -> Result<()> {
+    // A client that never finishes its request must not hold the connection's thread
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let request = http::read_request(&mut stream)?;
+    let peer_addr = stream.peer_addr()?;
+    if request.uri().path() != context.config.ws_path {
+        let response = respond(&request, peer_addr, context);
+        return http::write_response(&mut stream, response);
+    }
+
+    let id = next_connection_id();
+    let (sender, receiver) = mpsc::channel();
+    let info = ClientInfo {
+        id,
+        peer_addr,
+        user_agent: request
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from),
+        handle: ClientHandle::new(id, sender.clone()),
+        clients: context.clients.clone(),
+    };
+    let mut websocket = http::upgrade(Socket::new(stream, sender)?, &request)?;
+
+    // The app that rendered the page handles its connection,
+    // clients that connect without a page get a new one whose view replaces the page's
+    let session = request
+        .uri()
+        .query()
+        .and_then(|query| query_param(query, SESSION_PARAM))
+        .and_then(|token| context.sessions.take(token));
+    let (mut rendered, mut commands) = match session {
+        Some(rendered) => (rendered, Vec::new()),
+        None => {
+            let (rendered, _) = Rendered::new(T::new(Arc::clone(&context.shared)));
+            let commands = rendered.view.iter().map(Vdom::remount).collect();
+            (rendered, commands)
+        }
+    };
+    context.clients.insert(info.handle.clone());
+    let connected = rendered.app.on_connect(&info);
+    commands.extend(rendered.outgoing(connected));
+    let mut batches = Batches::new(context.config.acknowledge);
+    let mut liveness = Liveness::new(context.config.ping_interval);
+    serve_client(
+        &mut rendered,
+        commands,
+        &receiver,
+        &mut batches,
+        &mut liveness,
+        &mut websocket,
+    );
+    websocket.get_ref().shutdown();
+    context.clients.remove(id);
+    rendered.app.on_disconnect();
+    Ok(())
+}
+
+/// Sends the commands the client starts with, then runs the connection
+/// until the client disconnects or an unrecoverable error occurs
+fn serve_client<T: crate::App<S> + 'static, S>(
+    rendered: &mut Rendered<T>,
+    commands: Vec<crate::Command>,
+    receiver: &Receiver<Wakeup>,
+    batches: &mut Batches,
+    liveness: &mut Liveness,
+    websocket: &mut WebSocket<Socket>,
+) {
+    if !commands.is_empty() {
+        if let Err(err) = batches.send(websocket, &commands) {
+            rendered.app.on_error(&err);
+            return;
+        }
+    }
+
+    loop {
+        match websocket.read_message() {
+            Ok(msg) => {
+                liveness.heard();
+                if let Err(err) = handle_message(rendered, batches, websocket, msg) {
+                    let fatal = !matches!(err, Error::Decode(_));
+                    rendered.app.on_error(&err);
+                    if fatal {
+                        break;
+                    }
+                }
+                continue;
+            }
+            // Everything received so far has been read
+            Err(tungstenite::Error::Io(ref err)) if err.kind() == io::ErrorKind::WouldBlock => {}
+            // The close handshake is complete, or the client went away without one
+            Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+                break
+            }
+            Err(err) => {
+                rendered.app.on_error(&Error::Protocol(err));
+                break;
+            }
+        }
+
+        // Sleeps until the client sends something, commands are pushed
+        // or it is time to check on the client
+        let woken = match receiver.recv_timeout(liveness.until_check()) {
+            Ok(Wakeup::Pushed(commands)) => send_pushed(rendered, commands, batches, websocket),
+            Ok(Wakeup::Read(read)) => {
+                websocket.get_mut().received(read);
+                Ok(())
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(()),
+            // The connection keeps a sender in its ClientHandle
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Err(err) = woken {
+            rendered.app.on_error(&err);
+            break;
+        }
+        match liveness.check() {
+            Check::Alive => {}
+            Check::Ping => {
+                let ping = tungstenite::Message::Ping(Vec::new());
+                if let Err(err) = websocket.write_message(ping) {
+                    rendered.app.on_error(&Error::Protocol(err));
+                    break;
+                }
+            }
+            // The client went away without closing the connection
+            Check::Gone => break,
+        }
+    }
+}
+
+/// Starts the server with the default configuration,
+/// see `start_with_config_and_state`
+pub fn start<T: crate::App + Send + 'static>() -> Result<()> {
+    start_with_config_and_state::<T, ()>(ServerConfig::new(), ())
+}
+
+/// Starts the server with the given configuration,
+/// see `start_with_config_and_state`
+pub fn start_with_config<T: crate::App + Send + 'static>(config: ServerConfig) -> Result<()> {
+    start_with_config_and_state::<T, ()>(config, ())
+}
+
+/// Starts the server with the default configuration and state shared by every client,
+/// see `start_with_config_and_state`
+pub fn start_with_state<T, S>(state: S) -> Result<()>
+where
+    T: crate::App<S> + Send + 'static,
+    S: Send + Sync + 'static,
+{
+    start_with_config_and_state::<T, S>(ServerConfig::new(), state)
+}
+
+/// Starts the server, HTTP and WebSocket requests are served on the same port.
+/// Every page load creates a new instance of the type given as a type parameter,
+/// which renders the page and then handles the page's WebSocket connection on its own thread,
+/// each instance is given the same shared state.
+/// Only returns if the listener could not be bound
+pub fn start_with_config_and_state<T, S>(config: ServerConfig, state: S) -> Result<()>
+where
+    T: crate::App<S> + Send + 'static,
+    S: Send + Sync + 'static,
+{
+    let server = TcpListener::bind((config.address.as_str(), config.port))?;
+    serve::<T, S>(server, Arc::new(Context::new(config, state)));
+    Ok(())
+}
+
+/// Serves every connection the listener accepts on a thread of its own
+fn serve<T, S>(server: TcpListener, context: Arc<Context<T, S>>)
+where
+    T: crate::App<S> + Send + 'static,
+    S: Send + Sync + 'static,
+{
+    let expiring = Arc::clone(&context);
+    spawn(move || loop {
+        sleep(sessions::SESSION_TIMEOUT);
+        // Apps whose page never connected are disconnected like the ones whose page did
+        for mut rendered in expiring.sessions.expire() {
+            rendered.app.on_disconnect();
+        }
     });
 
-    let server = TcpListener::bind("localhost:1234").unwrap();
     for stream in server.incoming() {
-        spawn(move || {
-            let mut app = T::new();
-            let mut websocket = accept(stream.unwrap()).unwrap();
-            loop {
-                if let Ok(msg) = websocket.read_message() {
-                    println!("msg={}", msg);
-                    if msg.is_text() {
-                        let message: crate::Message =
-                            serde_json::from_str(msg.to_text().unwrap()).unwrap();
-                        let command = app.update(message);
-                        websocket
-                            .write_message(tungstenite::Message::Text(
-                                serde_json::to_string(&command).unwrap(),
-                            ))
-                            .unwrap();
+        match stream {
+            Ok(stream) => {
+                let context = Arc::clone(&context);
+                spawn(move || {
+                    if let Err(err) = run::<T, S>(stream, &context) {
+                        report(&err);
                     }
-                }
+                });
             }
-        });
+            Err(err) => report(&Error::Io(err)),
+        }
     }
 }
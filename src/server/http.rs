@@ -0,0 +1,107 @@
+//! A minimal HTTP/1.1 server, just enough to serve the page and static files
+//! and to upgrade a request to a WebSocket on the same listener.
+
+use std::io::{Read, Write};
+
+use tungstenite::handshake::server::create_response;
+use tungstenite::http::{HeaderMap, Request, Response, StatusCode, Version};
+use tungstenite::protocol::Role;
+use tungstenite::WebSocket;
+
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+mod tests;
+
+/// Requests with larger heads than this are rejected
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+/// Reads and parses the request line and headers from the stream
+pub(crate) fn read_request<R: Read>(stream: &mut R) -> Result<Request<()>> {
+    let mut buf = Vec::<u8>::new();
+    let mut chunk = [0u8; 1024];
+    loop {
+        let len = stream.read(&mut chunk)?;
+        if len == 0 {
+            return Err(Error::Http(
+                "connection closed before the request was read".to_string(),
+            ));
+        }
+        buf.extend_from_slice(&chunk[..len]);
+
+        let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let mut parsed = httparse::Request::new(&mut headers);
+        let status = parsed
+            .parse(&buf)
+            .map_err(|err| Error::Http(err.to_string()))?;
+        if let httparse::Status::Complete(_) = status {
+            let mut builder = Request::builder()
+                .method(parsed.method.unwrap_or("GET"))
+                .uri(parsed.path.unwrap_or("/"))
+                .version(if parsed.version == Some(0) {
+                    Version::HTTP_10
+                } else {
+                    Version::HTTP_11
+                });
+            for header in parsed.headers.iter() {
+                builder = builder.header(header.name, header.value);
+            }
+            return builder.body(()).map_err(|err| Error::Http(err.to_string()));
+        }
+
+        if buf.len() > MAX_HEAD_SIZE {
+            return Err(Error::Http("request head too large".to_string()));
+        }
+    }
+}
+
+fn write_headers(head: &mut String, headers: &HeaderMap) {
+    for (name, value) in headers {
+        if let Ok(value) = value.to_str() {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+}
+
+/// Writes a complete response and closes the connection
+pub(crate) fn write_response<W: Write>(stream: &mut W, response: Response<Vec<u8>>) -> Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status().as_u16(),
+        response.status().canonical_reason().unwrap_or("")
+    );
+    write_headers(&mut head, response.headers());
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body().len()
+    ));
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(response.body())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Completes the WebSocket handshake for a request that has already been read
+pub(crate) fn upgrade<S: Read + Write>(
+    mut stream: S,
+    request: &Request<()>,
+) -> Result<WebSocket<S>> {
+    let response = match create_response(request) {
+        Ok(response) => response,
+        Err(err) => {
+            let mut bad_request = Response::new(Vec::<u8>::new());
+            *bad_request.status_mut() = StatusCode::BAD_REQUEST;
+            write_response(&mut stream, bad_request)?;
+            return Err(Error::Handshake(err));
+        }
+    };
+
+    let mut head = "HTTP/1.1 101 Switching Protocols\r\n".to_string();
+    write_headers(&mut head, response.headers());
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+
+    Ok(WebSocket::from_raw_socket(stream, Role::Server, None))
+}
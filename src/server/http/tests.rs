@@ -0,0 +1,70 @@
+use std::io::{self, Cursor, Read};
+
+use super::{read_request, write_response};
+use crate::error::Error;
+use tungstenite::http::{Response, StatusCode};
+
+/// Returns one part per read, like a socket the request arrives on in pieces
+struct Parts(Vec<&'static [u8]>);
+
+impl Read for Parts {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Ok(0);
+        }
+        let part = self.0.remove(0);
+        buf[..part.len()].copy_from_slice(part);
+        Ok(part.len())
+    }
+}
+
+#[test]
+fn request_read_in_parts_is_parsed() {
+    let mut stream = Parts(vec![
+        b"GET /css/index.css?v=2 HT",
+        b"TP/1.1\r\nHost: localhost\r\nUser-Ag",
+        b"ent: test\r\n",
+        b"\r\n",
+    ]);
+    let request = read_request(&mut stream).unwrap();
+    assert_eq!(request.method(), "GET");
+    assert_eq!(request.uri().path(), "/css/index.css");
+    assert_eq!(request.uri().query(), Some("v=2"));
+    assert_eq!(request.headers()["user-agent"], "test");
+}
+
+#[test]
+fn request_with_an_oversized_head_is_rejected() {
+    let mut stream = Cursor::new(b"GET / HTTP/1.1\r\nX-Long: ".to_vec()).chain(io::repeat(b'a'));
+    match read_request(&mut stream) {
+        Err(Error::Http(message)) => assert_eq!(message, "request head too large"),
+        result => panic!("expected the head to be too large, got {:?}", result),
+    }
+}
+
+#[test]
+fn request_cut_short_is_rejected() {
+    let mut stream = Cursor::new(b"GET / HTTP/1.1\r\nHost: localhost\r\n".to_vec());
+    match read_request(&mut stream) {
+        Err(Error::Http(message)) => assert!(message.contains("connection closed")),
+        result => panic!("expected the request to be cut short, got {:?}", result),
+    }
+}
+
+#[test]
+fn malformed_request_is_rejected() {
+    let mut stream = Cursor::new(b"GET / HTTP/1.1\r\nBad Header\r\n\r\n".to_vec());
+    assert!(matches!(read_request(&mut stream), Err(Error::Http(_))));
+}
+
+#[test]
+fn response_is_written_with_its_length_and_closed() {
+    let mut response = Response::new(b"missing".to_vec());
+    *response.status_mut() = StatusCode::NOT_FOUND;
+    let mut written = Vec::new();
+    write_response(&mut written, response).unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 7\r\nConnection: close\r\n\r\nmissing"
+    );
+}
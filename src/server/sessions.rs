@@ -0,0 +1,87 @@
+//! Apps that rendered a page and are waiting for that page's WebSocket connection
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// Apps whose page has not connected within this time are dropped
+pub(crate) const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct Pending<T> {
+    app: T,
+    created: Instant,
+}
+
+pub(crate) struct Sessions<T> {
+    pending: Mutex<HashMap<String, Pending<T>>>,
+    timeout: Duration,
+}
+
+impl<T> Sessions<T> {
+    pub(crate) fn new() -> Self {
+        Self::with_timeout(SESSION_TIMEOUT)
+    }
+
+    pub(crate) fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            timeout,
+        }
+    }
+
+    /// Stores an app until its page connects, returns the token the page connects with
+    pub(crate) fn insert(&self, app: T) -> std::io::Result<String> {
+        let token = new_token()?;
+        self.lock().insert(
+            token.clone(),
+            Pending {
+                app,
+                created: Instant::now(),
+            },
+        );
+        Ok(token)
+    }
+
+    /// Removes and returns the app waiting for the given token,
+    /// an app that waited too long is left for `expire`
+    pub(crate) fn take(&self, token: &str) -> Option<T> {
+        let mut pending = self.lock();
+        match pending.get(token) {
+            Some(session) if session.created.elapsed() < self.timeout => {
+                pending.remove(token).map(|session| session.app)
+            }
+            _ => None,
+        }
+    }
+
+    /// Removes and returns the apps whose page did not connect in time
+    pub(crate) fn expire(&self) -> Vec<T> {
+        let mut pending = self.lock();
+        let expired: Vec<String> = pending
+            .iter()
+            .filter(|(_, session)| session.created.elapsed() >= self.timeout)
+            .map(|(token, _)| token.clone())
+            .collect();
+        expired
+            .iter()
+            .filter_map(|token| pending.remove(token))
+            .map(|session| session.app)
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Pending<T>>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Returns a token nobody can guess, read from the operating system's random source
+fn new_token() -> std::io::Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(std::io::Error::from)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use super::Sessions;
+
+#[test]
+fn app_is_taken_once_with_its_token() {
+    let sessions = Sessions::new();
+    let first = sessions.insert("first").unwrap();
+    let second = sessions.insert("second").unwrap();
+    assert_ne!(first, second);
+    assert_eq!(first.len(), 32);
+
+    assert_eq!(sessions.take(&second), Some("second"));
+    assert_eq!(sessions.take(&second), None);
+    assert_eq!(sessions.take(&first), Some("first"));
+}
+
+#[test]
+fn tokens_that_do_not_match_take_nothing() {
+    let sessions = Sessions::new();
+    let token = sessions.insert("app").unwrap();
+    assert_eq!(sessions.take(""), None);
+    assert_eq!(sessions.take(&token[1..]), None);
+    assert_eq!(sessions.take(&token), Some("app"));
+}
+
+#[test]
+fn apps_that_waited_too_long_are_expired_instead_of_taken() {
+    let sessions = Sessions::with_timeout(Duration::from_secs(0));
+    let token = sessions.insert("late").unwrap();
+    assert_eq!(sessions.take(&token), None);
+    assert_eq!(sessions.expire(), ["late"]);
+    assert!(sessions.expire().is_empty());
+}
+
+#[test]
+fn apps_still_waiting_are_not_expired() {
+    let sessions = Sessions::new();
+    let token = sessions.insert("waiting").unwrap();
+    assert!(sessions.expire().is_empty());
+    assert_eq!(sessions.take(&token), Some("waiting"));
+}
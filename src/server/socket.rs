@@ -0,0 +1,87 @@
+//! The socket a WebSocket connection is served on. Reads are done by a thread of their own
+//! and passed on through the connection's channel, so that the connection's thread sleeps
+//! until the client sends something or commands are pushed through its ClientHandle.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::Sender;
+use std::thread::spawn;
+
+use crate::connection::Wakeup;
+
+pub(crate) struct Socket {
+    stream: TcpStream,
+    /// Bytes read from the client that the WebSocket has not read yet
+    received: Vec<u8>,
+    /// Whether the client closed the connection after the received bytes
+    closed: bool,
+    /// The error reading after the received bytes failed with
+    error: Option<io::Error>,
+}
+
+impl Socket {
+    /// Starts reading the stream on a new thread, what it reads is sent to the connection
+    pub(crate) fn new(stream: TcpStream, sender: Sender<Wakeup>) -> io::Result<Self> {
+        stream.set_read_timeout(None)?;
+        let mut reader = stream.try_clone()?;
+        spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                let read = reader.read(&mut buf).map(|len| buf[..len].to_vec());
+                let done = !matches!(&read, Ok(bytes) if !bytes.is_empty());
+                if sender.send(Wakeup::Read(read)).is_err() || done {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            stream,
+            received: Vec::new(),
+            closed: false,
+            error: None,
+        })
+    }
+
+    /// Keeps what the reading thread read for the WebSocket
+    pub(crate) fn received(&mut self, read: io::Result<Vec<u8>>) {
+        match read {
+            Ok(bytes) if bytes.is_empty() => self.closed = true,
+            Ok(bytes) => self.received.extend(bytes),
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    /// Closes the connection, which also ends the reading thread
+    pub(crate) fn shutdown(&self) {
+        // The client may already have closed it
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+impl Read for Socket {
+    /// Reads the bytes received so far, fails with WouldBlock if there are none
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.received.is_empty() {
+            let len = buf.len().min(self.received.len());
+            buf[..len].copy_from_slice(&self.received[..len]);
+            self.received.drain(..len);
+            Ok(len)
+        } else if let Some(err) = self.error.take() {
+            Err(err)
+        } else if self.closed {
+            Ok(0)
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+}
+
+impl Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
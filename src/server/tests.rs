@@ -0,0 +1,148 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::spawn;
+
+use tungstenite::http::{Request, StatusCode};
+use tungstenite::WebSocket;
+
+use super::{respond, serve, static_file, Context, ServerConfig};
+use crate::builders::*;
+use crate::html::Node;
+use crate::{Command, Message};
+
+struct Counter {
+    count: u32,
+}
+
+impl crate::App for Counter {
+    fn new(_: Arc<()>) -> Self {
+        Self { count: 0 }
+    }
+
+    fn view(&self) -> Option<Node> {
+        Some(div().id("count").text(self.count.to_string()).node())
+    }
+
+    fn update(&mut self, _: Message) -> Vec<Command> {
+        self.count += 1;
+        Vec::new()
+    }
+}
+
+fn peer_addr() -> SocketAddr {
+    "127.0.0.1:1".parse().unwrap()
+}
+
+fn get(context: &Context<Counter, ()>, path: &str) -> (StatusCode, String) {
+    let request = Request::builder().uri(path).body(()).unwrap();
+    let response = respond(&request, peer_addr(), context);
+    let body = String::from_utf8(response.body().clone()).unwrap();
+    (response.status(), body)
+}
+
+/// Returns an empty directory for the test with the given name
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lowui-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn static_files_must_be_a_single_segment() {
+    assert_eq!(static_file("/css/index.css", "/css/"), Some("index.css"));
+    assert_eq!(static_file("/css/", "/css/"), None);
+    assert_eq!(static_file("/css/..", "/css/"), None);
+    assert_eq!(static_file("/css/../secret", "/css/"), None);
+    assert_eq!(static_file("/css/..\\secret", "/css/"), None);
+    assert_eq!(static_file("/css/dir/index.css", "/css/"), None);
+    assert_eq!(static_file("/html/index.css", "/css/"), None);
+}
+
+#[test]
+fn missing_files_are_not_found_and_unreadable_ones_fail() {
+    let dir = test_dir("static");
+    fs::write(dir.join("about.html"), "<p>About</p>").unwrap();
+    fs::create_dir(dir.join("folder")).unwrap();
+    let context = Context::<Counter, ()>::new(ServerConfig::new().html_dir(&dir), ());
+
+    let (status, body) = get(&context, "/html/about.html");
+    assert_eq!((status, body.as_str()), (StatusCode::OK, "<p>About</p>"));
+    assert_eq!(get(&context, "/html/missing.html").0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        get(&context, "/html/../Cargo.toml").0,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(get(&context, "/nowhere").0, StatusCode::NOT_FOUND);
+    assert_eq!(
+        get(&context, "/html/folder").0,
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Starts a server for Counter on a free port, returns its address
+fn start_counter() -> SocketAddr {
+    let server = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    let context = Arc::new(Context::<Counter, ()>::new(ServerConfig::new(), ()));
+    spawn(move || serve::<Counter, ()>(server, context));
+    addr
+}
+
+fn connect(addr: SocketAddr, path: &str) -> WebSocket<TcpStream> {
+    let stream = TcpStream::connect(addr).unwrap();
+    let url = format!("ws://{}{}", addr, path);
+    tungstenite::client(url.as_str(), stream).unwrap().0
+}
+
+fn receive(websocket: &mut WebSocket<TcpStream>) -> serde_json::Value {
+    let message = websocket.read_message().unwrap();
+    serde_json::from_str(message.to_text().unwrap()).unwrap()
+}
+
+fn click(websocket: &mut WebSocket<TcpStream>) {
+    let event = r#"{"Event":{"id":"count","event_type":"click","tag":"DIV"}}"#;
+    websocket
+        .write_message(tungstenite::Message::Text(event.to_string()))
+        .unwrap();
+}
+
+#[test]
+fn connection_without_a_page_is_sent_the_view_and_its_events_are_handled() {
+    let addr = start_counter();
+    let mut websocket = connect(addr, "/ws");
+
+    let remount = receive(&mut websocket);
+    assert_eq!(remount[0]["target"]["Id"], "count");
+    assert!(remount[0]["command_type"]["ReplaceElement"].is_object());
+
+    click(&mut websocket);
+    assert_eq!(
+        receive(&mut websocket),
+        serde_json::json!([{"command_type": {"SetText": "1"}, "target": {"Id": "count"}}])
+    );
+}
+
+#[test]
+fn page_is_served_and_its_connection_is_handled_by_the_app_that_rendered_it() {
+    let addr = start_counter();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut page = String::new();
+    stream.read_to_string(&mut page).unwrap();
+    assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(page.contains(r#"<div id="count">0</div>"#));
+
+    let start = page.find("/ws?session=").unwrap();
+    let path: String = page[start..].chars().take_while(|&c| c != '"').collect();
+    let mut websocket = connect(addr, &path);
+    // The page already shows the view, so the first message answers the click
+    click(&mut websocket);
+    assert_eq!(receive(&mut websocket)[0]["command_type"]["SetText"], "1");
+}
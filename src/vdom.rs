@@ -0,0 +1,422 @@
+//! Keeps the last view rendered for a connection and computes the commands
+//! that turn the client's copy of it into the next view.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::handlers;
+use crate::html::{events_attr, Attr, Handler, Node, EVENTS_ATTR};
+use crate::{Command, CommandType, Target};
+
+#[cfg(test)]
+mod tests;
+
+/// Elements without an id are given one starting with this prefix,
+/// commands can only target elements by id
+const ID_PREFIX: &str = "lowui-";
+
+pub(crate) struct Vdom {
+    last: Node,
+    next_id: u64,
+}
+
+impl Vdom {
+    /// Gives every element of the first view an id, returns the view as it should be rendered
+    pub(crate) fn mount(view: Node) -> (Self, Node) {
+        let mut vdom = Self {
+            last: Node::Text(String::new()),
+            next_id: 0,
+        };
+        let mut view = root(view);
+        vdom.assign_ids(&mut view);
+        vdom.last = view.clone();
+        (vdom, view)
+    }
+
+    /// Returns a command that replaces the client's copy of the view entirely,
+    /// for clients whose page was not rendered with this view
+    pub(crate) fn remount(&self) -> Command {
+        let id = id_of(attrs(&self.last)).unwrap_or_default();
+        Command::replace_element(id, self.last.clone())
+    }
+
+    /// Returns the last view
+    pub(crate) fn view_mut(&mut self) -> &mut Node {
+        &mut self.last
+    }
+
+    /// Returns the commands that turn the last view into the given one
+    pub(crate) fn update(&mut self, view: Node) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let last = std::mem::replace(&mut self.last, Node::Text(String::new()));
+        self.last = self.diff(&last, root(view), &mut commands);
+        commands
+    }
+
+    fn assign_ids(&mut self, node: &mut Node) {
+        if let Node::Element {
+            attrs,
+            children,
+            handlers,
+            ..
+        } = node
+        {
+            if id_of(attrs).is_none() {
+                attrs.push(Attr::new("id", format!("{}{}", ID_PREFIX, self.next_id)));
+                self.next_id += 1;
+            }
+            handlers.iter_mut().for_each(handlers::assign_id);
+            for child in children {
+                self.assign_ids(child);
+            }
+        }
+    }
+
+    /// Diffs two elements, old must have an id
+    fn diff(&mut self, old: &Node, mut new: Node, commands: &mut Vec<Command>) -> Node {
+        let (old_tag, old_key, old_attrs, old_children, old_handlers) = match old {
+            Node::Element {
+                tag,
+                key,
+                attrs,
+                children,
+                handlers,
+                ..
+            } => (*tag, key, attrs, children, handlers),
+            _ => unreachable!("only elements are diffed"),
+        };
+        let id = id_of(old_attrs).unwrap_or_default().to_string();
+
+        let same = match &new {
+            Node::Element {
+                tag,
+                key,
+                attrs,
+                children,
+                ..
+            } => {
+                *tag == old_tag
+                    && key == old_key
+                    && user_id(attrs) == user_id(old_attrs)
+                    && (keyed(old_children, children) || children_match(old_children, children))
+            }
+            _ => false,
+        };
+
+        if !same {
+            // The element keeps its id so it can still be found by later commands
+            set_default_id(&mut new, &id);
+            self.assign_ids(&mut new);
+            commands.push(Command::replace_element(id, new.clone()));
+            return new;
+        }
+
+        if let Node::Element {
+            attrs,
+            children,
+            handlers,
+            ..
+        } = &mut new
+        {
+            set_default_id_attr(attrs, &id);
+            diff_attrs(&id, old_attrs, attrs, commands);
+            diff_handlers(&id, old_handlers, handlers, commands);
+            let new_children = std::mem::take(children);
+            *children = if keyed(old_children, &new_children) {
+                self.diff_keyed(&id, old_children, new_children, commands)
+            } else {
+                self.diff_children(&id, old_children, new_children, commands)
+            };
+        }
+        new
+    }
+
+    /// Diffs children position by position, they must match, see children_match
+    fn diff_children(
+        &mut self,
+        parent_id: &str,
+        old: &[Node],
+        new: Vec<Node>,
+        commands: &mut Vec<Command>,
+    ) -> Vec<Node> {
+        let new_len = new.len();
+        let mut children = Vec::with_capacity(new_len);
+        for (i, mut child) in new.into_iter().enumerate() {
+            match old.get(i) {
+                Some(old_child @ Node::Element { .. }) => {
+                    children.push(self.diff(old_child, child, commands))
+                }
+                Some(Node::Text(old_text)) => {
+                    if let Node::Text(text) = &child {
+                        if text != old_text {
+                            commands.push(set_text(parent_id, i, old.len(), new_len, text));
+                        }
+                    }
+                    children.push(child)
+                }
+                // Equal markup
+                Some(_) => children.push(child),
+                None => {
+                    self.assign_ids(&mut child);
+                    commands.push(Command::append_child_element(parent_id, child.clone()));
+                    children.push(child);
+                }
+            }
+        }
+
+        for old_child in old.iter().skip(new_len) {
+            if let Some(id) = id_of(attrs(old_child)) {
+                commands.push(Command::remove_element(id));
+            }
+        }
+
+        children
+    }
+
+    /// Diffs children by key, elements whose key is still there are updated in place
+    /// and moved only if they are not part of the longest run that kept its order
+    fn diff_keyed(
+        &mut self,
+        parent_id: &str,
+        old: &[Node],
+        new: Vec<Node>,
+        commands: &mut Vec<Command>,
+    ) -> Vec<Node> {
+        let old_positions: HashMap<&str, usize> = old
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| Some((key_of(node)?, i)))
+            .collect();
+
+        // The position in old of each new child, None for added children
+        let mut sources = Vec::with_capacity(new.len());
+        let mut children = Vec::with_capacity(new.len());
+        for mut child in new {
+            let source = key_of(&child).and_then(|key| old_positions.get(key).copied());
+            match source {
+                Some(i) => children.push(self.diff(&old[i], child, commands)),
+                None => {
+                    self.assign_ids(&mut child);
+                    children.push(child);
+                }
+            }
+            sources.push(source);
+        }
+
+        let kept: Vec<usize> = sources.iter().flatten().copied().collect();
+        let kept_set: HashSet<usize> = kept.iter().copied().collect();
+        for (i, old_child) in old.iter().enumerate() {
+            if !kept_set.contains(&i) {
+                if let Some(id) = id_of(attrs(old_child)) {
+                    commands.push(Command::remove_element(id));
+                }
+            }
+        }
+
+        // Going backwards every child is placed before the one after it
+        let unmoved = longest_increasing(&kept);
+        let mut next_id: Option<String> = None;
+        for (child, source) in children.iter().zip(&sources).rev() {
+            let id = id_of(attrs(child)).unwrap_or_default().to_string();
+            let command = match (source, &next_id) {
+                (Some(i), _) if unmoved.contains(i) => None,
+                (Some(_), Some(next_id)) => Some(Command::move_element_before(&id, next_id)),
+                (Some(_), None) => Some(Command::move_element_to_end(&id)),
+                (None, Some(next_id)) => {
+                    Some(Command::insert_element_before(next_id, child.clone()))
+                }
+                (None, None) => Some(Command::append_child_element(parent_id, child.clone())),
+            };
+            commands.extend(command);
+            next_id = Some(id);
+        }
+
+        children
+    }
+}
+
+/// Commands target elements, so the root of a view must be one
+fn root(view: Node) -> Node {
+    match view {
+        Node::Element { .. } => view,
+        node => Node::new_el(
+            "div",
+            Vec::new(),
+            Vec::new(),
+            vec![node],
+            false,
+            None,
+            Vec::new(),
+        ),
+    }
+}
+
+fn set_default_id(node: &mut Node, id: &str) {
+    if let Node::Element { attrs, .. } = node {
+        set_default_id_attr(attrs, id);
+    }
+}
+
+fn set_default_id_attr(attrs: &mut Vec<Attr>, id: &str) {
+    if id_of(attrs).is_none() {
+        attrs.push(Attr::new("id", id));
+    }
+}
+
+fn attrs(node: &Node) -> &[Attr] {
+    match node {
+        Node::Element { attrs, .. } => attrs,
+        _ => &[],
+    }
+}
+
+fn key_of(node: &Node) -> Option<&str> {
+    match node {
+        Node::Element { key, .. } => key.as_deref(),
+        _ => None,
+    }
+}
+
+pub(crate) fn id_of(attrs: &[Attr]) -> Option<&str> {
+    attrs
+        .iter()
+        .find(|attr| attr.name == "id")
+        .and_then(|attr| attr.value.as_deref())
+}
+
+/// Returns the id given by the user, if any
+fn user_id(attrs: &[Attr]) -> Option<&str> {
+    id_of(attrs).filter(|id| !id.starts_with(ID_PREFIX))
+}
+
+/// Children are diffed by key if every child is an element with a key unique among its siblings
+fn keyed(old: &[Node], new: &[Node]) -> bool {
+    let unique_keys = |nodes: &[Node]| {
+        let mut keys = HashSet::new();
+        nodes
+            .iter()
+            .all(|node| key_of(node).is_some_and(|key| keys.insert(key)))
+    };
+    unique_keys(old) && unique_keys(new)
+}
+
+/// Children can be diffed position by position if elements stay elements, text stays text,
+/// markup is unchanged and removed children are elements. Changed text must be addressable,
+/// see text_addressable
+fn children_match(old: &[Node], new: &[Node]) -> bool {
+    let addressable = text_addressable(old, new);
+    let common = old.iter().zip(new.iter()).all(|pair| match pair {
+        (Node::Element { .. }, Node::Element { .. }) => true,
+        (Node::Text(old), Node::Text(new)) => old == new || addressable,
+        (Node::Raw(old), Node::Raw(new)) => old == new,
+        _ => false,
+    });
+    let removed = old
+        .iter()
+        .skip(new.len())
+        .all(|node| matches!(node, Node::Element { .. }));
+    common && removed
+}
+
+/// Text children can be changed in place if the text is the only child, or if the n-th child
+/// is the n-th node in the client, which it is unless there is markup, which can be any
+/// number of nodes, empty text, which is no node, or adjacent text, which is one node
+fn text_addressable(old: &[Node], new: &[Node]) -> bool {
+    let only_text = |nodes: &[Node]| matches!(nodes, [Node::Text(_)]);
+    let one_node_each = |nodes: &[Node]| {
+        nodes.iter().all(|node| match node {
+            Node::Raw(_) => false,
+            Node::Text(text) => !text.is_empty(),
+            Node::Element { .. } => true,
+        }) && nodes
+            .windows(2)
+            .all(|pair| !matches!(pair, [Node::Text(_), Node::Text(_)]))
+    };
+    (only_text(old) && only_text(new)) || (one_node_each(old) && one_node_each(new))
+}
+
+/// Returns the command that sets the text of the child at index,
+/// the parent's whole text is only set when the child was and stays its only one,
+/// as that also removes any other children before their own commands reach them
+fn set_text(parent_id: &str, index: usize, old_count: usize, count: usize, text: &str) -> Command {
+    if old_count == 1 && count == 1 {
+        Command::set_text(parent_id, text)
+    } else {
+        Command::set_child_text(parent_id, index, text)
+    }
+}
+
+/// Returns the values of a longest increasing subsequence, the values must be distinct
+fn longest_increasing(values: &[usize]) -> HashSet<usize> {
+    // ends[n] is the position of the smallest value ending an increasing subsequence of length n + 1
+    let mut ends: Vec<usize> = Vec::new();
+    let mut previous = vec![None; values.len()];
+    for (i, &value) in values.iter().enumerate() {
+        let n = ends.partition_point(|&end| values[end] < value);
+        if n > 0 {
+            previous[i] = Some(ends[n - 1]);
+        }
+        if n == ends.len() {
+            ends.push(i);
+        } else {
+            ends[n] = i;
+        }
+    }
+
+    let mut result = HashSet::new();
+    let mut position = ends.last().copied();
+    while let Some(i) = position {
+        result.insert(values[i]);
+        position = previous[i];
+    }
+    result
+}
+
+fn set_attribute(id: &str, attr: Attr) -> Command {
+    Command {
+        command_type: CommandType::SetAttribute(attr),
+        target: Some(Target::Id(id.to_string())),
+    }
+}
+
+fn diff_attrs(id: &str, old: &[Attr], new: &[Attr], commands: &mut Vec<Command>) {
+    for attr in new {
+        let unchanged = old
+            .iter()
+            .any(|old| old.name == attr.name && old.value == attr.value);
+        if !unchanged {
+            commands.push(set_attribute(id, attr.clone()));
+        }
+    }
+
+    for attr in old {
+        if !new.iter().any(|new| new.name == attr.name) {
+            commands.push(Command::remove_attribute(id, attr.name));
+        }
+    }
+}
+
+/// The nth closure for an event keeps the id of the nth old closure for that event,
+/// so the element's events only change when events are added, removed or given other options,
+/// the server replaces the closures
+fn diff_handlers(id: &str, old: &[Handler], new: &mut [Handler], commands: &mut Vec<Command>) {
+    let mut old_closures: Vec<&Handler> = old
+        .iter()
+        .filter(|handler| handler.callback.is_some())
+        .collect();
+    for handler in new.iter_mut().filter(|handler| handler.callback.is_some()) {
+        let same = old_closures
+            .iter()
+            .position(|old| old.event_kind == handler.event_kind);
+        match same {
+            Some(index) => handler.id = old_closures.remove(index).id.clone(),
+            None => handlers::assign_id(handler),
+        }
+    }
+
+    match (events_attr(old), events_attr(new)) {
+        (Some(old), Some(new)) if old.value == new.value => {}
+        (_, Some(new)) => commands.push(set_attribute(id, new)),
+        (Some(_), None) => commands.push(Command::remove_attribute(id, EVENTS_ATTR)),
+        (None, None) => {}
+    }
+}
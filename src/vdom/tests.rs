@@ -0,0 +1,315 @@
+use super::Vdom;
+use crate::builders::*;
+use crate::html::tests::Client;
+use crate::html::{EventKind, EventOptions, Node};
+use crate::Command;
+
+/// Returns the client's body with attributes in name order,
+/// attributes set by commands are added after the ones already there
+fn sorted_body(client: &mut Client) -> String {
+    client.eval(
+        "function sortAttributes(node) {
+            if (node.attributes) {
+                node.attributes.sort((a, b) => (a.name < b.name ? -1 : a.name > b.name ? 1 : 0));
+                node.childNodes.forEach(sortAttributes);
+            }
+        }
+        sortAttributes(document.body);
+        document.body.innerHTML",
+    )
+}
+
+/// Renders each view in turn on a client, checks the client ends up with the last view
+/// and returns the commands sent for each update
+fn render(views: Vec<Node>) -> Vec<Vec<Command>> {
+    let mut views = views.into_iter();
+    let (mut vdom, mounted) = Vdom::mount(views.next().unwrap());
+    let mut client = Client::new(vec![mounted]);
+    let mut sent = Vec::new();
+    for view in views {
+        let commands = vdom.update(view);
+        client.receive(commands.clone());
+        sent.push(commands);
+    }
+
+    let mut expected = Client::new(vec![vdom.last.clone()]);
+    assert_eq!(sorted_body(&mut client), sorted_body(&mut expected));
+    assert_eq!(client.warnings(), "");
+    sent
+}
+
+fn names(commands: &[Command]) -> Vec<String> {
+    commands
+        .iter()
+        .map(
+            |command| match serde_json::to_value(&command.command_type).unwrap() {
+                serde_json::Value::String(name) => name,
+                serde_json::Value::Object(map) => map.keys().next().unwrap().clone(),
+                value => panic!("unexpected command type {}", value),
+            },
+        )
+        .collect()
+}
+
+fn counter(count: u32) -> Node {
+    div()
+        .class("counter")
+        .el(span().text(count.to_string()))
+        .el(button().text("+"))
+        .node()
+}
+
+#[test]
+fn mount_gives_elements_ids_and_keeps_user_ids() {
+    let (_, mounted) = Vdom::mount(div().id("root").el(p().text("hi")).node());
+    assert_eq!(
+        mounted.into_html(),
+        r#"<div id="root"><p id="lowui-0">hi</p></div>"#
+    );
+}
+
+#[test]
+fn unchanged_view_sends_nothing() {
+    let sent = render(vec![counter(1), counter(1)]);
+    assert!(sent[0].is_empty());
+}
+
+#[test]
+fn changed_text_is_set_in_place() {
+    let sent = render(vec![counter(1), counter(2)]);
+    assert_eq!(names(&sent[0]), ["SetText"]);
+}
+
+#[test]
+fn changed_text_next_to_an_input_keeps_the_input() {
+    let view = |n: u32| {
+        div()
+            .text(format!("Count {}", n))
+            .el(input().id("name"))
+            .text("!")
+            .node()
+    };
+    let sent = render(vec![view(1), view(2)]);
+    assert_eq!(names(&sent[0]), ["SetChildText"]);
+}
+
+#[test]
+fn changed_text_with_removed_siblings_removes_them() {
+    let sent = render(vec![
+        p().text("a").el(b().text("x")).node(),
+        p().text("b").node(),
+    ]);
+    assert_eq!(names(&sent[0]), ["SetChildText", "RemoveElement"]);
+}
+
+#[test]
+fn changed_text_with_added_siblings_keeps_them() {
+    let sent = render(vec![
+        p().text("a").node(),
+        p().text("b").el(b().text("x")).node(),
+    ]);
+    assert_eq!(names(&sent[0]), ["SetChildText", "AppendChildElement"]);
+}
+
+#[test]
+fn changed_text_next_to_markup_replaces_its_element() {
+    let view = |n: u32| {
+        div()
+            .el(Node::raw_html("<b>a</b><i>b</i>"))
+            .text(n.to_string())
+            .node()
+    };
+    let sent = render(vec![view(1), view(2)]);
+    assert_eq!(names(&sent[0]), ["ReplaceElement"]);
+}
+
+#[test]
+fn changed_attributes_are_set_and_removed() {
+    let sent = render(vec![
+        div().class("a").title("t").node(),
+        div().class("b").hidden("hidden").node(),
+    ]);
+    assert_eq!(
+        names(&sent[0]),
+        ["SetAttribute", "SetAttribute", "RemoveAttribute"]
+    );
+}
+
+#[test]
+fn children_are_appended_and_removed() {
+    let item = |text: &str| li().text(text);
+    let sent = render(vec![
+        ul().el(item("a")).node(),
+        ul().el(item("a")).el(item("b")).el(item("c")).node(),
+        ul().el(item("a")).node(),
+    ]);
+    assert_eq!(
+        names(&sent[0]),
+        ["AppendChildElement", "AppendChildElement"]
+    );
+    assert_eq!(names(&sent[1]), ["RemoveElement", "RemoveElement"]);
+}
+
+#[test]
+fn changed_tag_replaces_element_and_keeps_it_targetable() {
+    let sent = render(vec![
+        div().el(p().text("a")).node(),
+        div().el(h1().text("a")).node(),
+        div().el(h1().class("big").text("a")).node(),
+    ]);
+    assert_eq!(names(&sent[0]), ["ReplaceElement"]);
+    assert_eq!(names(&sent[1]), ["SetAttribute"]);
+}
+
+#[test]
+fn changed_user_id_replaces_element() {
+    let sent = render(vec![
+        div().el(p().id("first").text("a")).node(),
+        div().el(p().id("second").text("a")).node(),
+    ]);
+    assert_eq!(names(&sent[0]), ["ReplaceElement"]);
+}
+
+#[test]
+fn root_that_is_not_an_element_is_wrapped() {
+    let sent = render(vec![Node::new_text("a"), Node::new_text("b")]);
+    assert_eq!(names(&sent[0]), ["SetText"]);
+}
+
+fn keyed_list(keys: &[&str]) -> Node {
+    ul().id("list")
+        .els(keys.iter().map(|key| li().key(*key).text(*key)).collect())
+        .node()
+}
+
+#[test]
+fn keyed_children_keep_their_ids() {
+    let (mut vdom, _) = Vdom::mount(keyed_list(&["a", "b"]));
+    vdom.update(keyed_list(&["b", "a"]));
+    assert_eq!(
+        vdom.last.clone().into_html(),
+        r#"<ul id="list"><li id="lowui-1">b</li><li id="lowui-0">a</li></ul>"#
+    );
+}
+
+#[test]
+fn keyed_child_moved_to_front_is_moved_once() {
+    let sent = render(vec![
+        keyed_list(&["a", "b", "c", "d"]),
+        keyed_list(&["d", "a", "b", "c"]),
+    ]);
+    assert_eq!(names(&sent[0]), ["MoveElementBefore"]);
+}
+
+#[test]
+fn keyed_child_moved_to_back_is_moved_to_end() {
+    let sent = render(vec![
+        keyed_list(&["a", "b", "c"]),
+        keyed_list(&["b", "c", "a"]),
+    ]);
+    assert_eq!(names(&sent[0]), ["MoveElementToEnd"]);
+}
+
+#[test]
+fn keyed_children_are_inserted_and_removed_in_place() {
+    let sent = render(vec![
+        keyed_list(&["a", "b", "c"]),
+        keyed_list(&["a", "x", "c"]),
+    ]);
+    assert_eq!(names(&sent[0]), ["RemoveElement", "InsertElementBefore"]);
+}
+
+#[test]
+fn keyed_children_are_reconciled_through_many_updates() {
+    render(vec![
+        keyed_list(&["a", "b", "c", "d", "e"]),
+        keyed_list(&["e", "c", "a", "f"]),
+        keyed_list(&[]),
+        keyed_list(&["b", "a"]),
+        keyed_list(&["g", "a", "h", "b", "i"]),
+        keyed_list(&["i", "h", "g", "b", "a"]),
+    ]);
+}
+
+#[test]
+fn changed_key_without_keyed_siblings_replaces_element() {
+    let sent = render(vec![
+        div().el(p().key("a").text("a")).el(p().text("b")).node(),
+        div().el(p().key("c").text("a")).el(p().text("b")).node(),
+    ]);
+    assert_eq!(names(&sent[0]), ["ReplaceElement"]);
+}
+
+fn clickable(clickable: bool) -> Node {
+    let mut button = button().text("+");
+    if clickable {
+        button = button.on(EventKind::Onclick, |_: &mut (), _: &crate::Message| {
+            Vec::new()
+        });
+    }
+    div().el(button).node()
+}
+
+#[test]
+fn handlers_keep_their_ids_across_updates() {
+    let sent = render(vec![
+        clickable(true),
+        clickable(true),
+        clickable(false),
+        clickable(true),
+    ]);
+    assert!(sent[0].is_empty());
+    assert_eq!(names(&sent[1]), ["RemoveAttribute"]);
+    assert_eq!(names(&sent[2]), ["SetAttribute"]);
+}
+
+#[test]
+fn changed_event_options_set_the_handler_attribute() {
+    let view = |options: EventOptions| {
+        div()
+            .el(input().on_with(
+                EventKind::Oninput,
+                options,
+                |_: &mut (), _: &crate::Message| Vec::new(),
+            ))
+            .node()
+    };
+    let sent = render(vec![
+        view(EventOptions::new()),
+        view(EventOptions::new().debounce_ms(200)),
+        view(EventOptions::new().debounce_ms(200)),
+    ]);
+    assert_eq!(names(&sent[0]), ["SetAttribute"]);
+    assert!(sent[1].is_empty());
+}
+
+#[test]
+fn closures_for_the_same_event_keep_their_own_ids() {
+    let view = || {
+        div()
+            .el(button()
+                .on(EventKind::Onclick, |_: &mut (), _: &crate::Message| {
+                    Vec::new()
+                })
+                .on(EventKind::Onclick, |_: &mut (), _: &crate::Message| {
+                    Vec::new()
+                }))
+            .node()
+    };
+    let ids = |view: &Node| match view {
+        Node::Element { children, .. } => match &children[0] {
+            Node::Element { handlers, .. } => handlers
+                .iter()
+                .map(|handler| handler.id.clone().unwrap())
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+
+    let (mut vdom, mounted) = Vdom::mount(view());
+    let mounted = ids(&mounted);
+    assert_ne!(mounted[0], mounted[1]);
+    assert!(vdom.update(view()).is_empty());
+    assert_eq!(ids(&vdom.last), mounted);
+}